@@ -5,7 +5,7 @@ import { authHeader, getAllParticipants, verifyResponse } from "./api_utils";
 import AdminParticipantsTab from "./AdminParticipantsTab";
 import AdminTranslationTab from "./AdminTranslationsTab";
 import AdminMistakesTab from "./AdminMistakesTab";
-import { PasswordContext } from "./PasswordContext";
+import { TokenContext } from "./TokenContext";
 import AdminCanonicalizationTab from "./NewCanonicalization";
 
 async function getAllTranslationSuggestions(): Promise<SuggestedTranslation[]> {
@@ -13,12 +13,17 @@ async function getAllTranslationSuggestions(): Promise<SuggestedTranslation[]> {
   return await response.json();
 }
 
-async function getAllMistakeSuggestions(): Promise<SuggestedMistake[]> {
-  const response = verifyResponse(await fetch("/api/suggest/mistakes"));
+async function getAllMistakeSuggestions(
+  token: string,
+): Promise<SuggestedMistake[]> {
+  // Authenticated, for reporter stats and witnesses
+  const response = verifyResponse(
+    await fetch("/api/suggest/mistakes", { headers: authHeader(token) }),
+  );
   return await response.json();
 }
 
-function AdminTabs({ password }: { password: string }) {
+function AdminTabs({ token }: { token: string }) {
   const [participants, setParticipants] = useState<string[]>([]);
   const [translationSuggestions, setTranslationSuggestions] = useState<
     SuggestedTranslation[]
@@ -28,12 +33,15 @@ function AdminTabs({ password }: { password: string }) {
   >([]);
 
   useEffect(() => {
+    if (!token) {
+      return;
+    }
     getAllParticipants().then((res) => setParticipants(res));
     getAllTranslationSuggestions().then((res) =>
       setTranslationSuggestions(res),
     );
-    getAllMistakeSuggestions().then((res) => setMistakeSuggestions(res));
-  }, []);
+    getAllMistakeSuggestions(token).then((res) => setMistakeSuggestions(res));
+  }, [token]);
 
   async function handleSelect(key: string | null): Promise<void> {
     if (key === "participants") {
@@ -41,13 +49,13 @@ function AdminTabs({ password }: { password: string }) {
     } else if (key === "translations") {
       setTranslationSuggestions(await getAllTranslationSuggestions());
     } else if (key === "mistakes") {
-      setMistakeSuggestions(await getAllMistakeSuggestions());
+      setMistakeSuggestions(await getAllMistakeSuggestions(token));
     }
   }
 
   return (
     <div style={{ direction: "rtl" }}>
-      <PasswordContext.Provider value={password}>
+      <TokenContext.Provider value={token}>
         <Tabs defaultActiveKey="participants" onSelect={handleSelect}>
           <Tab eventKey="participants" title="משתתפים">
             <AdminParticipantsTab
@@ -69,7 +77,7 @@ function AdminTabs({ password }: { password: string }) {
             <AdminMistakesTab
               suggestions={mistakeSuggestions}
               triggerRefresh={async () =>
-                setMistakeSuggestions(await getAllMistakeSuggestions())
+                setMistakeSuggestions(await getAllMistakeSuggestions(token))
               }
             />
           </Tab>
@@ -77,32 +85,55 @@ function AdminTabs({ password }: { password: string }) {
             <AdminCanonicalizationTab />
           </Tab>
         </Tabs>
-      </PasswordContext.Provider>
+      </TokenContext.Provider>
     </div>
   );
 }
 
 export default function Admin() {
   const [show, setShow] = useState<boolean>(true);
+  const [username, setUsername] = useState<string>("");
   const [password, setPassword] = useState<string>("");
+  const [token, setToken] = useState<string>("");
+  const [code, setCode] = useState<string | null>(null);
 
   async function handleClick() {
-    const response = verifyResponse(
-      await fetch("/api/auth", {
-        method: "GET",
-        headers: authHeader(password),
-      }),
-    );
-    const res: boolean = await response.json();
-    setShow(!res);
-    if (!res) {
-      setPassword("");
+    const login: Login = { username, password, code: code ?? undefined };
+    const response = await fetch("/api/login", {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify(login),
+    });
+    if (response.status === 401) {
+      const error: ErrorBody = await response.json();
+      if (error.code === "TotpRequired") {
+        setCode("");
+      } else {
+        setPassword("");
+      }
+      return;
     }
+    setPassword("");
+    setCode(null);
+    const session: SessionToken = await verifyResponse(response).json();
+    setToken(session.token);
+    setShow(false);
+  }
+
+  async function handleLogout() {
+    verifyResponse(
+      await fetch("/api/logout", { method: "POST", headers: authHeader(token) }),
+    );
+    setToken("");
+    setShow(true);
   }
 
   return (
     <div style={{ direction: "rtl", textAlign: "right" }}>
-      <AdminTabs password={password} />
+      <Button variant="outline-secondary" onClick={handleLogout}>
+        התנתק
+      </Button>
+      <AdminTabs token={token} />
       <Modal
         style={{ direction: "rtl", textAlign: "right" }}
         show={show}
@@ -113,12 +144,24 @@ export default function Admin() {
           <Modal.Title>האם אתה מנהל?</Modal.Title>
         </Modal.Header>
         <Modal.Body>
+          <Form.Control
+            placeholder="שם משתמש"
+            value={username}
+            onChange={(e) => setUsername(e.target.value)}
+          />
           <Form.Control
             type="password"
             placeholder="סיסמה"
             value={password}
             onChange={(e) => setPassword(e.target.value)}
           />
+          {code !== null && (
+            <Form.Control
+              placeholder="קוד אימות"
+              value={code}
+              onChange={(e) => setCode(e.target.value)}
+            />
+          )}
         </Modal.Body>
         <Modal.Footer>
           <Button type="submit" onClick={handleClick}>
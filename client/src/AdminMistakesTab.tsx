@@ -9,39 +9,35 @@ import {
 } from "react-bootstrap";
 import { authHeader, verifyResponse } from "./api_utils";
 import { useContext } from "react";
-import { PasswordContext } from "./PasswordContext";
+import { TokenContext } from "./TokenContext";
 import { CanonicalizeUnknownWord } from "./NewCanonicalization";
 
 async function discardSuggestion(
   suggestion: DiscardMistakeSuggestion,
-  password: string,
+  token: string,
 ): Promise<void> {
   verifyResponse(
     await fetch("/api/suggest/mistakes", {
       method: "DELETE",
-      headers: { ...authHeader(password), "Content-Type": "application/json" },
+      headers: { ...authHeader(token), "Content-Type": "application/json" },
       body: JSON.stringify(suggestion),
     }),
   );
 }
 
-async function acceptSuggestion(
-  suggestion: MistakeSuggestion,
-  password: string,
-): Promise<void> {
-  const mistake: MistakeReport = {
-    name: suggestion.name,
-    mistake: suggestion.mistake,
-  };
-  verifyResponse(
-    await fetch("/api/mistakes", {
-      method: "POST",
-      headers: { ...authHeader(password), "Content-Type": "application/json" },
-      body: JSON.stringify(mistake),
-    }),
+function ReporterStatsBadge({ stats }: { stats: ReporterStats }) {
+  return (
+    <div className="text-muted small">
+      ✅ {stats.accepted} ❌ {stats.rejected} ⏳ {stats.pending}
+    </div>
   );
 }
 
+/** Reporters with the best record first, unmoderated reporters last */
+function acceptanceRate(suggestion: SuggestedMistake): number {
+  return suggestion.reporter_stats?.acceptance_rate ?? -1;
+}
+
 function MistakeSuggestionCard({
   suggestion,
   triggerRefresh,
@@ -49,13 +45,12 @@ function MistakeSuggestionCard({
   suggestion: SuggestedMistake;
   triggerRefresh: () => Promise<void>;
 }) {
-  const password = useContext(PasswordContext);
+  const token = useContext(TokenContext);
 
   async function handleAcceptClick() {
-    await acceptSuggestion(suggestion.mistake, password);
     await discardSuggestion(
       { id: suggestion.mistake.id, accepted: true },
-      password,
+      token,
     );
     await triggerRefresh();
   }
@@ -63,7 +58,7 @@ function MistakeSuggestionCard({
   async function handleDiscardClick() {
     await discardSuggestion(
       { id: suggestion.mistake.id, accepted: false },
-      password,
+      token,
     );
     await triggerRefresh();
   }
@@ -82,6 +77,7 @@ function MistakeSuggestionCard({
                       <th>שגיאה</th>
                       <th>הקשר</th>
                       <th>מדווח</th>
+                      <th>עדים</th>
                     </tr>
                   </thead>
                   <tbody>
@@ -93,7 +89,17 @@ function MistakeSuggestionCard({
                         />
                       </td>
                       <td>{suggestion.mistake.context}</td>
-                      <td>{suggestion.reporter}</td>
+                      <td>
+                        {suggestion.reporter}
+                        {suggestion.reporter_stats && (
+                          <ReporterStatsBadge
+                            stats={suggestion.reporter_stats}
+                          />
+                        )}
+                      </td>
+                      <td title={suggestion.witnesses?.join(", ")}>
+                        {suggestion.witnesses?.length ?? 1}
+                      </td>
                     </tr>
                   </tbody>
                 </Table>
@@ -131,14 +137,16 @@ export default function AdminMistakesTab({
         <p>אין דיווחים על שגיאות כרגע!</p>
       ) : (
         <Carousel interval={null} wrap={false}>
-          {suggestions.map((suggestion) => (
-            <Carousel.Item key={suggestion.mistake.id}>
-              <MistakeSuggestionCard
-                suggestion={suggestion}
-                triggerRefresh={triggerRefresh}
-              />
-            </Carousel.Item>
-          ))}
+          {[...suggestions]
+            .sort((a, b) => acceptanceRate(b) - acceptanceRate(a))
+            .map((suggestion) => (
+              <Carousel.Item key={suggestion.mistake.id}>
+                <MistakeSuggestionCard
+                  suggestion={suggestion}
+                  triggerRefresh={triggerRefresh}
+                />
+              </Carousel.Item>
+            ))}
         </Carousel>
       )}
     </>
@@ -1,17 +1,17 @@
 import { useContext, useState } from "react";
 import { Button, Form, ListGroup } from "react-bootstrap";
 import { authHeader, verifyResponse } from "./api_utils";
-import { PasswordContext } from "./PasswordContext";
+import { TokenContext } from "./TokenContext";
 
 async function addParticipant(
-  password: string,
+  token: string,
   participant: string,
 ): Promise<void> {
   verifyResponse(
     await fetch("/api/participants", {
       method: "POST",
       headers: {
-        ...authHeader(password),
+        ...authHeader(token),
         ...{ "Content-Type": "application/json" },
       },
       body: JSON.stringify(participant),
@@ -36,12 +36,12 @@ function AddParticipantForm({
   onSubmit: () => void;
   triggerRefresh: () => Promise<void>;
 }) {
-  const password = useContext(PasswordContext);
+  const token = useContext(TokenContext);
   const [participant, setParticipant] = useState<string>("");
 
   async function handleSubmit(e: React.SyntheticEvent) {
     e.preventDefault();
-    await addParticipant(password, participant);
+    await addParticipant(token, participant);
     setParticipant("");
     await triggerRefresh();
     onSubmit();
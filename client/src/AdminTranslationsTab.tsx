@@ -11,7 +11,7 @@ import {
 } from "react-bootstrap";
 import { authHeader, verifyResponse } from "./api_utils";
 import { useContext, useEffect, useState } from "react";
-import { PasswordContext } from "./PasswordContext";
+import { TokenContext } from "./TokenContext";
 import { CanonicalizeUnknownWord } from "./NewCanonicalization";
 
 async function canonicalize(word: string): Promise<string> {
@@ -19,32 +19,15 @@ async function canonicalize(word: string): Promise<string> {
   return await response.json();
 }
 
-async function discardSuggestion(id: number, password: string): Promise<void> {
+async function discardSuggestion(
+  suggestion: DiscardTranslationSuggestion,
+  token: string,
+): Promise<void> {
   verifyResponse(
     await fetch("/api/suggest/translations", {
       method: "DELETE",
-      headers: { ...authHeader(password), "Content-Type": "application/json" },
-      body: JSON.stringify(id),
-    }),
-  );
-}
-
-async function acceptSuggestion(
-  english: string,
-  hebrew: string,
-  suggestor: string,
-  password: string,
-): Promise<void> {
-  const translation: Translation = { english: english, hebrew: hebrew };
-  const translation_addition: TranslationAddition = {
-    translation: translation,
-    suggestor: suggestor,
-  };
-  verifyResponse(
-    await fetch("/api/translations", {
-      method: "POST",
-      headers: { ...authHeader(password), "Content-Type": "application/json" },
-      body: JSON.stringify(translation_addition),
+      headers: { ...authHeader(token), "Content-Type": "application/json" },
+      body: JSON.stringify(suggestion),
     }),
   );
 }
@@ -56,7 +39,7 @@ function AcceptTranslationForm({
   suggestion: SuggestedTranslation;
   onSubmit: () => Promise<void>;
 }) {
-  const password = useContext(PasswordContext);
+  const token = useContext(TokenContext);
   const [hebrew, setHebrew] = useState<string>(suggestion.translation.hebrew);
   const [canonicalEnglish, setCanonicalEnglish] = useState<string>("");
 
@@ -68,13 +51,10 @@ function AcceptTranslationForm({
 
   async function handleSubmit(e: React.SyntheticEvent) {
     e.preventDefault();
-    await acceptSuggestion(
-      suggestion.translation.english,
-      hebrew,
-      suggestion.suggestor,
-      password,
+    await discardSuggestion(
+      { id: suggestion.translation.id, accepted: true, hebrew: hebrew },
+      token,
     );
-    await discardSuggestion(suggestion.translation.id, password);
     await onSubmit();
   }
 
@@ -106,11 +86,14 @@ function TranslationSuggestionCard({
   suggestion: SuggestedTranslation;
   triggerRefresh: () => Promise<void>;
 }) {
-  const password = useContext(PasswordContext);
+  const token = useContext(TokenContext);
   const [showForm, setShowForm] = useState<boolean>(false);
 
   async function handleDiscardClick() {
-    await discardSuggestion(suggestion.translation.id, password);
+    await discardSuggestion(
+      { id: suggestion.translation.id, accepted: false },
+      token,
+    );
     await triggerRefresh();
   }
 
@@ -1,18 +1,18 @@
 import { useContext, useEffect, useState } from "react";
 import { authHeader, isKnownWord, verifyResponse } from "./api_utils";
-import { PasswordContext } from "./PasswordContext";
+import { TokenContext } from "./TokenContext";
 import { Button, Form, Modal } from "react-bootstrap";
 
 async function addCanonicalization(
   word: string,
   canonical: string,
-  password: string,
+  token: string,
 ): Promise<void> {
   const request: CanonicalRequest = { word: word, canonical: canonical };
   verifyResponse(
     await fetch("/api/canonicalize", {
       method: "POST",
-      headers: { ...authHeader(password), "Content-Type": "application/json" },
+      headers: { ...authHeader(token), "Content-Type": "application/json" },
       body: JSON.stringify(request),
     }),
   );
@@ -25,12 +25,12 @@ export function NewCanonicalizationForm({
   word: string;
   onSubmit: () => void;
 }) {
-  const password = useContext(PasswordContext);
+  const token = useContext(TokenContext);
   const [canonical, setCanonical] = useState<string>(word);
 
   async function handleSubmit(e: React.SyntheticEvent) {
     e.preventDefault();
-    await addCanonicalization(word, canonical, password);
+    await addCanonicalization(word, canonical, token);
     setCanonical("");
     onSubmit();
   }
@@ -1,3 +0,0 @@
-import { Context, createContext } from "react";
-
-export const PasswordContext: Context<string> = createContext("");
@@ -0,0 +1,3 @@
+import { Context, createContext } from "react";
+
+export const TokenContext: Context<string> = createContext("");
@@ -12,8 +12,8 @@ export async function isKnownWord(word: string): Promise<boolean> {
   return await response.json();
 }
 
-export function authHeader(pass: string): { Authorization: string } {
-  return { Authorization: `Basic ${btoa("admin:" + pass)}` };
+export function authHeader(token: string): { Authorization: string } {
+  return { Authorization: `Bearer ${token}` };
 }
 
 export function verifyResponse(response: Response): Response {
@@ -21,6 +21,12 @@ export function verifyResponse(response: Response): Response {
     throw Error(`Failed fetch with internal server error`);
   } else if (response.status === 401) {
     throw Error("Unauthorized!");
+  } else if (response.status === 429) {
+    throw Error(
+      `Too many attempts, retry in ${response.headers.get("Retry-After")}s`,
+    );
+  } else if (!response.ok) {
+    throw Error(`Failed fetch with status ${response.status}`);
   } else {
     return response;
   }
@@ -1,8 +1,42 @@
 /* This file is generated and managed by tsync */
 
+/** Machine-readable kind of a failed request */
+type ErrorCode =
+  | "NotFound" | "Conflict" | "Validation" | "Unauthorized" | "TotpRequired" | "Forbidden" | "TooManyRequests" | "Internal";
+
+/** The JSON body of every failed request */
+interface ErrorBody {
+  code: ErrorCode;
+  message: string;
+}
+
 interface MistakeReport {
   name: string;
   mistake: string;
+  context?: string;
+}
+
+interface Participant {
+  name: string;
+  display_name?: string;
+  team?: string;
+  avatar_url?: string;
+  active: boolean;
+}
+
+interface ParticipantMetadata {
+  display_name?: string;
+  team?: string;
+  avatar_url?: string;
+  active: boolean;
+}
+
+interface ParticipantRename {
+  name: string;
+}
+
+interface ParticipantMerge {
+  into: string;
 }
 
 interface MistakeSuggestion {
@@ -15,6 +49,48 @@ interface MistakeSuggestion {
 interface SuggestedMistake {
   mistake: MistakeSuggestion;
   reporter: string;
+  /** Only filled in when listing pending suggestions to moderators */
+  reporter_stats?: ReporterStats;
+  /**
+   * Every reporter who suggested it, the original reporter first.
+   * Only filled in when listing pending suggestions to moderators.
+   */
+  witnesses?: Array<string>;
+}
+
+/** How a reporter's mistake suggestions were moderated, across every event */
+interface ReporterStats {
+  reporter: string;
+  submitted: number;
+  accepted: number;
+  rejected: number;
+  pending: number;
+  /** Accepted by an auto-approval rule, which doesn't count towards the acceptance rate */
+  auto_approved: number;
+  /** Of the suggestions moderated by an admin so far, none if there aren't any */
+  acceptance_rate?: number;
+}
+
+/** How an archived suggestion was decided on */
+type SuggestionDecision =
+  | "Accepted" | "Rejected" | "Auto" | "Reverted";
+
+interface SuggestionOutcome {
+  /** The pending suggestion, or if it was auto-approved, the archived one */
+  id: number;
+  auto_approved: boolean;
+  /** Reporters who suggested it so far, when identical suggestions were merged */
+  witnesses: number;
+}
+
+/** A suggestion accepted by an auto-approval rule, which an admin may revert */
+interface AutoApprovedMistake {
+  id: number;
+  suggestion: SuggestedMistake;
+  /** The mistake report it was recorded as */
+  report_id: number;
+  decision: SuggestionDecision;
+  timestamp: string;
 }
 
 interface DiscardMistakeSuggestion {
@@ -32,6 +108,74 @@ interface PersonMistake {
   counted_mistake: CountedMistake;
 }
 
+/** A single report of a mistake, as kept in the mistakes log */
+interface LoggedMistake {
+  id: number;
+  event: number;
+  name: string;
+  mistake: string;
+  reporter: string;
+  context?: string;
+  /** Unknown for reports from before the mistakes log, which no date range includes */
+  timestamp?: string;
+  /** Retracted reports stay in the log but are no longer counted */
+  retracted: boolean;
+}
+
+/** Changes to a reported mistake, fields left out keep their current value */
+interface MistakeCorrection {
+  name?: string;
+  mistake?: string;
+}
+
+type CorrectionAction =
+  | "Retracted" | "Corrected";
+
+interface MistakeCorrectionRecord {
+  id: number;
+  report_id: number;
+  action: CorrectionAction;
+  old_name: string;
+  old_mistake: string;
+  new_name?: string;
+  new_mistake?: string;
+  moderator: string;
+  timestamp: string;
+}
+
+type AppealStatus =
+  | "Pending" | "Upheld" | "Overturned";
+
+/** A participant disputing a mistake report */
+interface NewAppeal {
+  reason: string;
+}
+
+interface Appeal {
+  id: number;
+  report: LoggedMistake;
+  reason: string;
+  appellant: string;
+  status: AppealStatus;
+  moderator?: string;
+  /** The moderator's explanation of their decision */
+  response?: string;
+  created_at: string;
+  decided_at?: string;
+}
+
+interface AppealQuery {
+  event?: number;
+  /** All appeals unless set */
+  status?: AppealStatus;
+}
+
+interface AppealDecision {
+  /** Retracts the report if set, otherwise upholds it */
+  overturn: boolean;
+  response?: string;
+}
+
 interface PersonMistakes {
   name: string;
   counted_mistakes: Array<CountedMistake>;
@@ -53,12 +197,195 @@ interface TranslationSuggestion {
   hebrew: string;
 }
 
+/** Accepting a suggestion may override its Hebrew translation */
+interface DiscardTranslationSuggestion {
+  id: number;
+  accepted: boolean;
+  hebrew?: string;
+}
+
 interface SuggestedTranslation {
   translation: TranslationSuggestion;
   suggestor: string;
 }
 
+/** Narrows the pending suggestions down to those matching every given field */
+interface SuggestionFilter {
+  /** Also matches mistake suggestions this reporter witnessed, for moderators */
+  reporter?: string;
+  /** Mistake suggestions only */
+  participant?: string;
+  /** Mistakes match by canonical word where known, translations by either side */
+  word?: string;
+}
+
+/** Accepts or rejects many suggestions at once, selected by id, by filter, or both */
+interface SuggestionBatch {
+  ids?: Array<number>;
+  filter: SuggestionFilter;
+  accepted: boolean;
+  /** Mistake suggestions only, defaults to the active event */
+  event?: number;
+}
+
+/** What happened to one suggestion of a batch */
+interface BatchItemResult {
+  id: number;
+  /** Only set if it failed, in which case it was left pending */
+  error?: ErrorBody;
+}
+
+interface BatchReport {
+  succeeded: number;
+  failed: number;
+  results: Array<BatchItemResult>;
+}
+
 interface CanonicalRequest {
   word: string;
   canonical: string;
 }
+
+type EventStatus =
+  | "Planned" | "Active" | "Finished";
+
+interface WeekEvent {
+  id: number;
+  name: string;
+  start_date: string;
+  end_date: string;
+  status: EventStatus;
+}
+
+interface NewWeekEvent {
+  name: string;
+  start_date: string;
+  end_date: string;
+}
+
+/** Selects the event a request refers to, defaulting to the active one */
+interface EventQuery {
+  event?: number;
+}
+
+interface MistakesLogQuery {
+  event?: number;
+  word?: string;
+}
+
+interface LeaderboardEntry {
+  /** Participants with the same total share a rank */
+  rank: number;
+  name: string;
+  total: number;
+  distinct_words: number;
+  top_word?: string;
+}
+
+/** Dates are inclusive and formatted as YYYY-MM-DD */
+interface LeaderboardQuery {
+  event?: number;
+  from?: string;
+  to?: string;
+}
+
+/** What an admin may do, a `SuperAdmin` may do everything */
+type Role =
+  | "Moderator" | "DictionaryEditor" | "SuperAdmin";
+
+interface AdminUser {
+  username: string;
+  role: Role;
+}
+
+interface NewAdminUser {
+  username: string;
+  password: string;
+  role: Role;
+}
+
+interface Login {
+  username: string;
+  password: string;
+  /** From the authenticator app, or a recovery code. Required once TOTP is enrolled. */
+  code?: string;
+}
+
+interface PasswordChange {
+  current_password: string;
+  new_password: string;
+}
+
+/** Shown once: the secret for the authenticator app, and recovery codes in case it's lost */
+interface TotpEnrollment {
+  secret: string;
+  otpauth_uri: string;
+  recovery_codes: Array<string>;
+}
+
+interface TotpCode {
+  code: string;
+}
+
+/**
+ * Required to replace or remove a confirmed enrollment, so a stolen session token can't
+ * turn off the second factor: either the password or a current code
+ */
+interface TotpReauthentication {
+  password?: string;
+  code?: string;
+}
+
+/** Sent as `Authorization: Bearer <token>` until it expires or is revoked */
+interface SessionToken {
+  token: string;
+  username: string;
+  role: Role;
+  expires_at: string;
+}
+
+/** What an admin (or, for suggestions, a reporter) changed */
+type AuditAction =
+  | "AddEvent" | "SetEventStatus" | "AddParticipant" | "UpdateParticipant" | "RenameParticipant" | "MergeParticipant" | "RemoveParticipant" | "ReportMistake" | "RetractMistake" | "CorrectMistake" | "SuggestMistake" | "AcceptMistakeSuggestion" | "RejectMistakeSuggestion" | "SuggestTranslation" | "AddTranslation" | "AcceptTranslationSuggestion" | "RejectTranslationSuggestion" | "AddCanonical" | "AddAdminUser" | "RemoveAdminUser" | "ChangePassword" | "Login" | "Logout" | "RevokeSessions" | "EnrollTotp" | "ConfirmTotp" | "RemoveTotp" | "BlockReporter" | "UnblockReporter" | "AutoApproveMistakeSuggestion" | "RevertAutoApproval" | "WitnessMistakeSuggestion" | "AppealMistake" | "UpholdAppeal" | "OverturnAppeal";
+
+interface AuditEntry {
+  id: number;
+  actor: string;
+  ip?: string;
+  action: AuditAction;
+  /** JSON of what was changed, before and after the change */
+  before?: string;
+  after?: string;
+  timestamp: string;
+}
+
+interface AuditQuery {
+  actor?: string;
+  action?: AuditAction;
+  /** Inclusive dates, as YYYY-MM-DD */
+  from?: string;
+  to?: string;
+  /** Starting from 1, newest entries first */
+  page?: number;
+  page_size?: number;
+}
+
+interface AuditPage {
+  entries: Array<AuditEntry>;
+  total: number;
+  page: number;
+  page_size: number;
+}
+
+/** A reporter (or IP) whose suggestions are refused */
+interface BlockedReporter {
+  reporter: string;
+  reason?: string;
+  blocked_by: string;
+  timestamp: string;
+}
+
+interface NewBlockedReporter {
+  reporter: string;
+  reason?: string;
+}
@@ -1,17 +1,38 @@
-use crate::auth::authorize;
+use crate::auth::{
+    authorize, check_new_password, hash_password, new_session_token, set_password, token_hash,
+    verify_password, LoginOutcome, SESSION_LIFETIME_HOURS,
+};
+use crate::client_ip::ClientIp;
+use crate::config::Config;
 use crate::error::AppError;
-use crate::hebrew_db::HebrewDb;
+use crate::hebrew_db::{Actor, HebrewDb};
+use crate::reporters::ReporterResolver;
+use crate::throttle::{LoginAttempt, LoginThrottle, SuggestionLimiter, ThrottleKey};
+use crate::totp;
 use crate::types::{
-    CanonicalRequest, DiscardMistakeSuggestion, MistakeReport, MistakeSuggestion, PersonMistake,
-    PersonMistakes, SuggestedMistake, SuggestedTranslation, Translation, TranslationAddition,
-    TranslationSuggestion,
+    AdminUser, Appeal, AppealDecision, AppealQuery, AuditPage, AuditQuery, AutoApprovedMistake,
+    BatchItemResult, BatchReport, BlockedReporter, CanonicalRequest, DiscardMistakeSuggestion,
+    DiscardTranslationSuggestion, EventQuery, EventStatus, LeaderboardEntry, LeaderboardQuery,
+    LoggedMistake, Login, MistakeCorrection, MistakeCorrectionRecord, MistakeReport,
+    MistakeSuggestion, MistakesLogQuery, NewAdminUser, NewAppeal, NewBlockedReporter, NewWeekEvent,
+    Participant, ParticipantMerge, ParticipantMetadata, ParticipantRename, PasswordChange,
+    PersonMistake, PersonMistakes, ReporterStats, Role, SessionToken, SuggestedMistake,
+    SuggestedTranslation, SuggestionBatch, SuggestionFilter, SuggestionOutcome, TotpCode,
+    TotpEnrollment, TotpReauthentication, Translation, TranslationAddition, TranslationSuggestion,
+    WeekEvent,
 };
-use axum::extract::Path;
-use axum::routing::{delete, get, post};
+use axum::async_trait;
+use axum::extract::Request;
+use axum::extract::{FromRequestParts, Path, Query};
+use axum::handler::Handler;
+use axum::http::request::Parts;
+use axum::http::Method;
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::Response;
+use axum::routing::{on, MethodFilter, MethodRouter};
 use axum::Router;
 use axum::{extract::State, Json};
-use axum_client_ip::XForwardedFor;
-use axum_extra::headers::authorization::Basic;
+use axum_extra::headers::authorization::Bearer;
 use axum_extra::headers::Authorization;
 use axum_extra::TypedHeader;
 use std::net::IpAddr;
@@ -21,39 +42,377 @@ use tracing::instrument;
 #[derive(Clone, Debug)]
 pub struct AppState {
     db: Arc<Mutex<HebrewDb>>,
+    login_throttle: Arc<Mutex<LoginThrottle>>,
+    suggestion_limiter: Arc<Mutex<SuggestionLimiter>>,
+    reporters: Arc<ReporterResolver>,
+    config: Arc<Config>,
 }
 
 impl AppState {
     pub fn new() -> Result<Self, AppError> {
+        let config = Config::from_env()?;
         Ok(Self {
             db: Arc::new(Mutex::new(HebrewDb::new()?)),
+            login_throttle: Arc::default(),
+            suggestion_limiter: Arc::default(),
+            reporters: Arc::new(ReporterResolver::new(&config)),
+            config: Arc::new(config),
         })
     }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
 }
 
-pub fn routes() -> Router<AppState> {
-    Router::new()
-        .route("/auth", get(auth))
-        .route("/participants", get(participants).post(add_participant))
-        .route("/mistakes", get(all_mistakes).post(report_mistake))
-        .route("/mistakes/:name", get(mistakes))
-        .route("/translations", get(all_translations).post(add_translation))
-        .route("/translate/:english", get(translate))
-        .route(
+/// Who may call an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Anyone may read
+    Public,
+    /// Anyone may write, on purpose (logging in, suggestions)
+    Anonymous,
+    /// Any logged in admin
+    LoggedIn,
+    /// Admins with this role (or super admins)
+    Role(Role),
+}
+
+pub struct Endpoint {
+    pub method: Method,
+    pub path: &'static str,
+    pub access: Access,
+    handler: MethodRouter<AppState>,
+}
+
+fn endpoint<H, T>(method: Method, path: &'static str, access: Access, handler: H) -> Endpoint
+where
+    H: Handler<T, AppState>,
+    T: 'static,
+{
+    let filter = MethodFilter::try_from(method.clone()).expect("Unsupported endpoint method");
+    Endpoint {
+        method,
+        path,
+        access,
+        handler: on(filter, handler),
+    }
+}
+
+/// Every endpoint of the API. Access is enforced by `guard`, not by the handlers themselves.
+pub fn endpoints() -> Vec<Endpoint> {
+    vec![
+        endpoint(Method::GET, "/auth", Access::LoggedIn, auth),
+        endpoint(Method::POST, "/login", Access::Anonymous, login),
+        endpoint(Method::POST, "/logout", Access::LoggedIn, logout),
+        endpoint(
+            Method::GET,
+            "/audit",
+            Access::Role(Role::SuperAdmin),
+            audit_log,
+        ),
+        endpoint(Method::POST, "/password", Access::LoggedIn, change_password),
+        endpoint(Method::POST, "/totp", Access::LoggedIn, enroll_totp),
+        endpoint(
+            Method::POST,
+            "/totp/confirm",
+            Access::LoggedIn,
+            confirm_totp,
+        ),
+        endpoint(Method::DELETE, "/totp", Access::LoggedIn, remove_own_totp),
+        endpoint(
+            Method::DELETE,
+            "/admins/:username/totp",
+            Access::Role(Role::SuperAdmin),
+            remove_totp,
+        ),
+        endpoint(
+            Method::GET,
+            "/admins",
+            Access::Role(Role::SuperAdmin),
+            admin_users,
+        ),
+        endpoint(
+            Method::POST,
+            "/admins",
+            Access::Role(Role::SuperAdmin),
+            add_admin_user,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/admins/:username",
+            Access::Role(Role::SuperAdmin),
+            remove_admin_user,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/admins/:username/sessions",
+            Access::Role(Role::SuperAdmin),
+            revoke_admin_sessions,
+        ),
+        endpoint(Method::GET, "/events", Access::Public, events),
+        endpoint(
+            Method::POST,
+            "/events",
+            Access::Role(Role::SuperAdmin),
+            add_event,
+        ),
+        endpoint(Method::GET, "/events/active", Access::Public, active_event),
+        endpoint(Method::GET, "/events/:id", Access::Public, event),
+        endpoint(
+            Method::POST,
+            "/events/:id/status",
+            Access::Role(Role::SuperAdmin),
+            set_event_status,
+        ),
+        endpoint(Method::GET, "/participants", Access::Public, participants),
+        endpoint(
+            Method::POST,
+            "/participants",
+            Access::Role(Role::Moderator),
+            add_participant,
+        ),
+        endpoint(
+            Method::GET,
+            "/participants/:name",
+            Access::Public,
+            participant,
+        ),
+        endpoint(
+            Method::POST,
+            "/participants/:name",
+            Access::Role(Role::Moderator),
+            update_participant,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/participants/:name",
+            Access::Role(Role::Moderator),
+            remove_participant,
+        ),
+        endpoint(
+            Method::POST,
+            "/participants/:name/rename",
+            Access::Role(Role::Moderator),
+            rename_participant,
+        ),
+        endpoint(
+            Method::POST,
+            "/participants/:name/merge",
+            Access::Role(Role::Moderator),
+            merge_participant,
+        ),
+        endpoint(Method::GET, "/mistakes", Access::Public, all_mistakes),
+        endpoint(
+            Method::POST,
+            "/mistakes",
+            Access::Role(Role::Moderator),
+            report_mistake,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/mistakes",
+            Access::Role(Role::Moderator),
+            decrement_mistake,
+        ),
+        endpoint(
+            Method::GET,
+            "/mistakes/corrections",
+            Access::Public,
+            mistake_corrections,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/mistakes/reports/:id",
+            Access::Role(Role::Moderator),
+            retract_mistake,
+        ),
+        endpoint(
+            Method::POST,
+            "/mistakes/reports/:id",
+            Access::Role(Role::Moderator),
+            correct_mistake,
+        ),
+        endpoint(
+            Method::POST,
+            "/mistakes/reports/:id/appeal",
+            Access::Anonymous,
+            appeal_mistake,
+        ),
+        endpoint(
+            Method::GET,
+            "/appeals",
+            Access::Role(Role::Moderator),
+            appeals,
+        ),
+        endpoint(
+            Method::POST,
+            "/appeals/:id",
+            Access::Role(Role::Moderator),
+            decide_appeal,
+        ),
+        endpoint(Method::GET, "/mistakes/:name", Access::Public, mistakes),
+        endpoint(
+            Method::GET,
+            "/mistakes/:name/log",
+            Access::Public,
+            mistakes_log,
+        ),
+        endpoint(Method::GET, "/leaderboard", Access::Public, leaderboard),
+        endpoint(
+            Method::GET,
+            "/translations",
+            Access::Public,
+            all_translations,
+        ),
+        endpoint(
+            Method::POST,
+            "/translations",
+            Access::Role(Role::DictionaryEditor),
+            add_translation,
+        ),
+        endpoint(
+            Method::GET,
+            "/translate/:english",
+            Access::Public,
+            translate,
+        ),
+        endpoint(
+            Method::GET,
             "/suggest/mistakes",
-            delete(discard_mistake_suggestion)
-                .post(suggest_mistake)
-                .get(all_mistake_suggestions),
-        )
-        .route(
+            Access::Public,
+            all_mistake_suggestions,
+        ),
+        endpoint(
+            Method::POST,
+            "/suggest/mistakes",
+            Access::Anonymous,
+            suggest_mistake,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/suggest/mistakes",
+            Access::Role(Role::Moderator),
+            discard_mistake_suggestion,
+        ),
+        endpoint(
+            Method::POST,
+            "/suggest/mistakes/batch",
+            Access::Role(Role::Moderator),
+            moderate_mistake_suggestions,
+        ),
+        endpoint(
+            Method::GET,
+            "/suggest/mistakes/auto",
+            Access::Role(Role::Moderator),
+            auto_approved_mistakes,
+        ),
+        endpoint(
+            Method::POST,
+            "/suggest/mistakes/auto/:id/revert",
+            Access::Role(Role::Moderator),
+            revert_auto_approval,
+        ),
+        endpoint(
+            Method::GET,
             "/suggest/translations",
-            delete(discard_translation_suggestion)
-                .post(suggest_translation)
-                .get(all_translation_suggestions),
-        )
-        .route("/known/:word", get(is_known_word))
-        .route("/canonicalize", post(add_canonical))
-        .route("/canonicalize/:word", get(get_canonical))
+            Access::Public,
+            all_translation_suggestions,
+        ),
+        endpoint(
+            Method::POST,
+            "/suggest/translations",
+            Access::Anonymous,
+            suggest_translation,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/suggest/translations",
+            Access::Role(Role::DictionaryEditor),
+            discard_translation_suggestion,
+        ),
+        endpoint(
+            Method::POST,
+            "/suggest/translations/batch",
+            Access::Role(Role::DictionaryEditor),
+            moderate_translation_suggestions,
+        ),
+        endpoint(
+            Method::GET,
+            "/reporters",
+            Access::Role(Role::Moderator),
+            reporter_stats,
+        ),
+        endpoint(
+            Method::GET,
+            "/blocklist",
+            Access::Role(Role::Moderator),
+            blocked_reporters,
+        ),
+        endpoint(
+            Method::POST,
+            "/blocklist",
+            Access::Role(Role::Moderator),
+            block_reporter,
+        ),
+        endpoint(
+            Method::DELETE,
+            "/blocklist/:reporter",
+            Access::Role(Role::Moderator),
+            unblock_reporter,
+        ),
+        endpoint(Method::GET, "/known/:word", Access::Public, is_known_word),
+        endpoint(
+            Method::POST,
+            "/canonicalize",
+            Access::Role(Role::DictionaryEditor),
+            add_canonical,
+        ),
+        endpoint(
+            Method::GET,
+            "/canonicalize/:word",
+            Access::Public,
+            get_canonical,
+        ),
+    ]
+}
+
+pub fn routes(state: AppState) -> Router {
+    endpoints()
+        .into_iter()
+        .fold(Router::new(), |router, endpoint| {
+            tracing::debug!(
+                "Routing {} {} with {:?} access",
+                endpoint.method,
+                endpoint.path,
+                endpoint.access
+            );
+            let handler = endpoint
+                .handler
+                .route_layer(from_fn_with_state((state.clone(), endpoint.access), guard));
+            router.route(endpoint.path, handler)
+        })
+        .with_state(state)
+}
+
+/// Rejects requests not allowed by the endpoint's access, and hands the session to the handler
+async fn guard(
+    State((state, access)): State<(AppState, Access)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let role = match access {
+        Access::Public | Access::Anonymous => return Ok(next.run(request).await),
+        Access::LoggedIn => None,
+        Access::Role(role) => Some(role),
+    };
+    let (mut parts, body) = request.into_parts();
+    let session = Session::from_request_parts(&mut parts, &state).await?;
+    if role.is_some_and(|role| !session.has_role(role)) {
+        return Err(AppError::Forbidden);
+    }
+    parts.extensions.insert(session);
+    Ok(next.run(Request::from_parts(parts, body)).await)
 }
 
 #[instrument(skip(state), err)]
@@ -64,43 +423,215 @@ pub async fn is_known_word(
     Ok(Json(state.db.lock().unwrap().is_known_word(&word)?))
 }
 
+#[instrument(skip(state), err)]
+pub async fn events(State(state): State<AppState>) -> Result<Json<Vec<WeekEvent>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().events()?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn active_event(State(state): State<AppState>) -> Result<Json<WeekEvent>, AppError> {
+    Ok(Json(state.db.lock().unwrap().active_event()?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn event(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<WeekEvent>, AppError> {
+    Ok(Json(state.db.lock().unwrap().event(id)?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn add_event(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<NewWeekEvent>,
+) -> Result<Json<WeekEvent>, AppError> {
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .add_event(payload, &session.actor())?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn set_event_status(
+    State(state): State<AppState>,
+    session: Session,
+    Path(id): Path<i64>,
+    Json(payload): Json<EventStatus>,
+) -> Result<Json<WeekEvent>, AppError> {
+    Ok(Json(state.db.lock().unwrap().set_event_status(
+        id,
+        payload,
+        &session.actor(),
+    )?))
+}
+
 #[instrument(skip(state), err)]
 pub async fn all_mistakes(
     State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
 ) -> Result<Json<Vec<PersonMistakes>>, AppError> {
-    Ok(Json(state.db.lock().unwrap().all_mistakes()?))
+    Ok(Json(state.db.lock().unwrap().all_mistakes(query.event)?))
 }
 
 #[instrument(skip(state), err)]
 pub async fn mistakes(
     State(state): State<AppState>,
     Path(name): Path<String>,
+    Query(query): Query<EventQuery>,
 ) -> Result<Json<PersonMistakes>, AppError> {
-    Ok(Json(state.db.lock().unwrap().mistakes(&name)?))
+    Ok(Json(state.db.lock().unwrap().mistakes(&name, query.event)?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn leaderboard(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Vec<LeaderboardEntry>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().leaderboard(query)?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn mistakes_log(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<MistakesLogQuery>,
+) -> Result<Json<Vec<LoggedMistake>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().mistakes_log(
+        &name,
+        query.word.as_deref(),
+        query.event,
+    )?))
 }
 
 #[instrument(skip(state), err)]
 pub async fn report_mistake(
     State(state): State<AppState>,
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
+    session: Session,
+    Json(payload): Json<MistakeReport>,
+) -> Result<Json<PersonMistake>, AppError> {
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .report_mistake(payload, &session.actor())?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn decrement_mistake(
+    State(state): State<AppState>,
+    session: Session,
     Json(payload): Json<MistakeReport>,
 ) -> Result<Json<PersonMistake>, AppError> {
-    authenticate(authorization).await?;
-    Ok(Json(state.db.lock().unwrap().report_mistake(payload)?))
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .decrement_mistake(payload, &session.actor())?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn retract_mistake(
+    State(state): State<AppState>,
+    session: Session,
+    Path(id): Path<i64>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .retract_mistake(id, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn correct_mistake(
+    State(state): State<AppState>,
+    session: Session,
+    Path(id): Path<i64>,
+    Json(payload): Json<MistakeCorrection>,
+) -> Result<Json<LoggedMistake>, AppError> {
+    Ok(Json(state.db.lock().unwrap().correct_mistake(
+        id,
+        payload,
+        &session.actor(),
+    )?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn mistake_corrections(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+) -> Result<Json<Vec<MistakeCorrectionRecord>>, AppError> {
+    Ok(Json(
+        state.db.lock().unwrap().mistake_corrections(query.event)?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn appeal_mistake(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Path(report_id): Path<i64>,
+    Json(payload): Json<NewAppeal>,
+) -> Result<Json<Appeal>, AppError> {
+    let actor = anonymous_actor(&state, ip).await?;
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .appeal_mistake(report_id, &payload, &actor)?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn appeals(
+    State(state): State<AppState>,
+    Query(query): Query<AppealQuery>,
+) -> Result<Json<Vec<Appeal>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().appeals(query)?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn decide_appeal(
+    State(state): State<AppState>,
+    session: Session,
+    Path(id): Path<i64>,
+    Json(payload): Json<AppealDecision>,
+) -> Result<Json<Appeal>, AppError> {
+    Ok(Json(state.db.lock().unwrap().decide_appeal(
+        id,
+        payload,
+        &session.actor(),
+    )?))
 }
 
 #[instrument(skip(state), err)]
 pub async fn suggest_mistake(
     State(state): State<AppState>,
-    XForwardedFor(ips): XForwardedFor,
+    ClientIp(ip): ClientIp,
     Json(payload): Json<MistakeSuggestion>,
-) -> Result<Json<i64>, AppError> {
-    let reporter = reporter(ips);
+) -> Result<Json<SuggestionOutcome>, AppError> {
+    let actor = anonymous_actor(&state, ip).await?;
     Ok(Json(state.db.lock().unwrap().suggest_mistake(
         SuggestedMistake {
             mistake: payload,
-            reporter,
+            reporter: actor.name.clone(),
+            reporter_stats: None,
+            witnesses: None,
         },
+        state.config.duplicate_window,
+        &state.config.auto_approval,
+        &actor,
     )?))
 }
 
@@ -114,26 +645,30 @@ pub async fn all_translations(
 #[instrument(skip(state), err)]
 pub async fn add_translation(
     State(state): State<AppState>,
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
+    session: Session,
     Json(payload): Json<TranslationAddition>,
 ) -> Result<(), AppError> {
-    authenticate(authorization).await?;
-    state.db.lock().unwrap().add_translation(payload)?;
+    state
+        .db
+        .lock()
+        .unwrap()
+        .add_translation(payload, &session.actor())?;
     Ok(())
 }
 
 #[instrument(skip(state), err)]
 pub async fn suggest_translation(
     State(state): State<AppState>,
-    XForwardedFor(ips): XForwardedFor,
+    ClientIp(ip): ClientIp,
     Json(payload): Json<TranslationSuggestion>,
 ) -> Result<Json<i64>, AppError> {
-    let suggestor = reporter(ips);
+    let actor = anonymous_actor(&state, ip).await?;
     Ok(Json(state.db.lock().unwrap().suggest_translation(
         SuggestedTranslation {
             translation: payload,
-            suggestor,
+            suggestor: actor.name.clone(),
         },
+        &actor,
     )?))
 }
 
@@ -148,56 +683,148 @@ pub async fn translate(
 #[instrument(skip(state), err)]
 pub async fn discard_mistake_suggestion(
     State(state): State<AppState>,
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
+    session: Session,
     Json(payload): Json<DiscardMistakeSuggestion>,
+) -> Result<Json<Option<PersonMistake>>, AppError> {
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .discard_mistake_suggestion(payload, &session.actor())?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn discard_translation_suggestion(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<DiscardTranslationSuggestion>,
 ) -> Result<(), AppError> {
-    authenticate(authorization).await?;
     state
         .db
         .lock()
         .unwrap()
-        .discard_mistake_suggestion(payload)?;
+        .discard_translation_suggestion(payload, &session.actor())?;
     Ok(())
 }
 
 #[instrument(skip(state), err)]
-pub async fn discard_translation_suggestion(
+pub async fn moderate_mistake_suggestions(
     State(state): State<AppState>,
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
-    Json(payload): Json<i64>,
-) -> Result<(), AppError> {
-    authenticate(authorization).await?;
-    state
+    session: Session,
+    Json(payload): Json<SuggestionBatch>,
+) -> Result<Json<BatchReport>, AppError> {
+    let results = state
         .db
         .lock()
         .unwrap()
-        .discard_translation_suggestion(payload)?;
-    Ok(())
+        .moderate_mistake_suggestions(&payload, &session.actor())?;
+    Ok(Json(batch_report(results)))
+}
+
+#[instrument(skip(state), err)]
+pub async fn moderate_translation_suggestions(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<SuggestionBatch>,
+) -> Result<Json<BatchReport>, AppError> {
+    let results = state
+        .db
+        .lock()
+        .unwrap()
+        .moderate_translation_suggestions(&payload, &session.actor())?;
+    Ok(Json(batch_report(results)))
+}
+
+fn batch_report(results: Vec<(i64, anyhow::Result<()>)>) -> BatchReport {
+    let results: Vec<BatchItemResult> = results
+        .into_iter()
+        .map(|(id, result)| BatchItemResult {
+            id,
+            error: result.err().map(|err| AppError::from(err).body()),
+        })
+        .collect();
+    let failed = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .count() as u32;
+    BatchReport {
+        succeeded: results.len() as u32 - failed,
+        failed,
+        results,
+    }
 }
 
 #[instrument(skip(state), err)]
 pub async fn all_mistake_suggestions(
     State(state): State<AppState>,
+    session: Option<Session>,
+    Query(query): Query<EventQuery>,
+    Query(filter): Query<SuggestionFilter>,
 ) -> Result<Json<Vec<SuggestedMistake>>, AppError> {
-    Ok(Json(state.db.lock().unwrap().all_mistake_suggestions()?))
+    // Listed publicly, but only moderators see who else reported what and how reliably
+    let details = session.is_some_and(|session| session.has_role(Role::Moderator));
+    Ok(Json(state.db.lock().unwrap().all_mistake_suggestions(
+        query.event,
+        &filter,
+        details,
+    )?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn auto_approved_mistakes(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+) -> Result<Json<Vec<AutoApprovedMistake>>, AppError> {
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .auto_approved_mistakes(query.event)?,
+    ))
+}
+
+#[instrument(skip(state), err)]
+pub async fn revert_auto_approval(
+    State(state): State<AppState>,
+    session: Session,
+    Path(id): Path<i64>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .revert_auto_approval(id, &session.actor())?;
+    Ok(())
 }
 
 #[instrument(skip(state), err)]
 pub async fn all_translation_suggestions(
     State(state): State<AppState>,
+    Query(filter): Query<SuggestionFilter>,
 ) -> Result<Json<Vec<SuggestedTranslation>>, AppError> {
     Ok(Json(
-        state.db.lock().unwrap().all_translation_suggestions()?,
+        state
+            .db
+            .lock()
+            .unwrap()
+            .all_translation_suggestions(&filter)?,
     ))
 }
 
 #[instrument(skip(state), err)]
 pub async fn add_canonical(
     State(state): State<AppState>,
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
+    session: Session,
     Json(payload): Json<CanonicalRequest>,
 ) -> Result<(), AppError> {
-    state.db.lock().unwrap().add_canonical(payload)?;
+    state
+        .db
+        .lock()
+        .unwrap()
+        .add_canonical(payload, &session.actor())?;
     Ok(())
 }
 
@@ -210,40 +837,497 @@ pub async fn get_canonical(
 }
 
 #[instrument(skip(state), err)]
-pub async fn participants(State(state): State<AppState>) -> Result<Json<Vec<String>>, AppError> {
-    Ok(Json(state.db.lock().unwrap().participants()?))
+pub async fn participants(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+) -> Result<Json<Vec<String>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().participants(query.event)?))
 }
 
 #[instrument(skip(state), err)]
 pub async fn add_participant(
     State(state): State<AppState>,
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
+    session: Session,
     Json(payload): Json<String>,
 ) -> Result<(), AppError> {
-    authenticate(authorization).await?;
-    state.db.lock().unwrap().add_participant(&payload)?;
+    state
+        .db
+        .lock()
+        .unwrap()
+        .add_participant(&payload, &session.actor())?;
     Ok(())
 }
 
-#[instrument]
-pub async fn auth(
-    TypedHeader(authorization): TypedHeader<Authorization<Basic>>,
-) -> Result<Json<bool>, AppError> {
-    Ok(Json(authorize(authorization.password()).await?))
+#[instrument(skip(state), err)]
+pub async fn participant(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<EventQuery>,
+) -> Result<Json<Participant>, AppError> {
+    Ok(Json(
+        state.db.lock().unwrap().participant(&name, query.event)?,
+    ))
 }
 
-async fn authenticate(header: Authorization<Basic>) -> Result<(), AppError> {
-    if authorize(header.password()).await? {
-        Ok(())
-    } else {
-        Err(AppError::AuthError)
+#[instrument(skip(state), err)]
+pub async fn update_participant(
+    State(state): State<AppState>,
+    session: Session,
+    Path(name): Path<String>,
+    Query(query): Query<EventQuery>,
+    Json(payload): Json<ParticipantMetadata>,
+) -> Result<Json<Participant>, AppError> {
+    Ok(Json(state.db.lock().unwrap().update_participant(
+        &name,
+        payload,
+        query.event,
+        &session.actor(),
+    )?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn remove_participant(
+    State(state): State<AppState>,
+    session: Session,
+    Path(name): Path<String>,
+    Query(query): Query<EventQuery>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .remove_participant(&name, query.event, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn rename_participant(
+    State(state): State<AppState>,
+    session: Session,
+    Path(name): Path<String>,
+    Query(query): Query<EventQuery>,
+    Json(payload): Json<ParticipantRename>,
+) -> Result<Json<Participant>, AppError> {
+    Ok(Json(state.db.lock().unwrap().rename_participant(
+        &name,
+        &payload.name,
+        query.event,
+        &session.actor(),
+    )?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn merge_participant(
+    State(state): State<AppState>,
+    session: Session,
+    Path(name): Path<String>,
+    Query(query): Query<EventQuery>,
+    Json(payload): Json<ParticipantMerge>,
+) -> Result<Json<Participant>, AppError> {
+    Ok(Json(state.db.lock().unwrap().merge_participant(
+        &name,
+        &payload.into,
+        query.event,
+        &session.actor(),
+    )?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn admin_users(State(state): State<AppState>) -> Result<Json<Vec<AdminUser>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().admin_users()?))
+}
+
+#[instrument(skip(state, payload), err)]
+pub async fn add_admin_user(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<NewAdminUser>,
+) -> Result<Json<AdminUser>, AppError> {
+    let admin = AdminUser {
+        username: payload.username,
+        role: payload.role,
+    };
+    check_new_password(&payload.password)?;
+    let hash = hash_password(&payload.password)?;
+    state
+        .db
+        .lock()
+        .unwrap()
+        .add_admin_user(&admin, &hash, &session.actor())?;
+    Ok(Json(admin))
+}
+
+/// Also logs the admin out of every other session
+#[instrument(skip(state, payload), err)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    session: Session,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<PasswordChange>,
+) -> Result<(), AppError> {
+    let actor = session.actor();
+    let username = session.admin.username;
+    // Throttled like logins, or a stolen session token could guess the password
+    let keys = [ThrottleKey::Ip(ip), ThrottleKey::account(&username)];
+    let attempt = LoginAttempt::begin(&state.login_throttle, &keys)
+        .map_err(|retry_after| AppError::TooManyRequests { retry_after })?;
+    if verify_password(&state.db, &username, &payload.current_password)
+        .await?
+        .is_none()
+    {
+        attempt.failed();
+        return Err(AppError::Unauthorized);
     }
+    check_new_password(&payload.new_password)?;
+    let hash = hash_password(&payload.new_password)?;
+    set_password(&state.db, &username, &hash, &session.token_hash, &actor).await?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn remove_admin_user(
+    State(state): State<AppState>,
+    session: Session,
+    Path(username): Path<String>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .remove_admin_user(&username, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn reporter_stats(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ReporterStats>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().reporter_stats()?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn blocked_reporters(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<BlockedReporter>>, AppError> {
+    Ok(Json(state.db.lock().unwrap().blocked_reporters()?))
+}
+
+#[instrument(skip(state), err)]
+pub async fn block_reporter(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<NewBlockedReporter>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .block_reporter(&payload, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn unblock_reporter(
+    State(state): State<AppState>,
+    session: Session,
+    Path(reporter): Path<String>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .unblock_reporter(&reporter, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, AppError> {
+    Ok(Json(state.db.lock().unwrap().audit_log(query)?))
 }
 
-fn reporter(ips: Vec<IpAddr>) -> String {
-    let ip = ips.first();
-    match ip {
-        None => "No IP".to_owned(),
-        Some(ip) => dns_lookup::lookup_addr(ip).unwrap_or(ip.to_string()),
+#[instrument(skip(state, payload), err)]
+pub async fn login(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<Login>,
+) -> Result<Json<SessionToken>, AppError> {
+    let account = ThrottleKey::account(&payload.username);
+    let keys = [ThrottleKey::Ip(ip), account.clone()];
+    // Begun before verifying, so locked out guesses don't cost a password hash
+    let attempt = LoginAttempt::begin(&state.login_throttle, &keys)
+        .map_err(|retry_after| AppError::TooManyRequests { retry_after })?;
+    let outcome = authorize(
+        &state.db,
+        &payload.username,
+        &payload.password,
+        payload.code.as_deref(),
+    )
+    .await?;
+    let admin = match outcome {
+        LoginOutcome::Valid(admin) => admin,
+        LoginOutcome::CodeRequired => return Err(AppError::TotpRequired),
+        LoginOutcome::Invalid => {
+            attempt.failed();
+            return Err(AppError::Unauthorized);
+        }
+    };
+    attempt.succeeded(&[account]);
+    let token = new_session_token();
+    let expires_at = state.db.lock().unwrap().add_admin_session(
+        &token_hash(&token),
+        &admin,
+        SESSION_LIFETIME_HOURS,
+        &Actor {
+            name: admin.username.clone(),
+            ip: Some(ip.to_string()),
+        },
+    )?;
+    Ok(Json(SessionToken {
+        token,
+        username: admin.username,
+        role: admin.role,
+        expires_at,
+    }))
+}
+
+#[instrument(skip(state), err)]
+pub async fn logout(State(state): State<AppState>, session: Session) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .remove_admin_session(&session.token_hash, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(skip(state), err)]
+pub async fn revoke_admin_sessions(
+    State(state): State<AppState>,
+    session: Session,
+    Path(username): Path<String>,
+) -> Result<Json<usize>, AppError> {
+    Ok(Json(
+        state
+            .db
+            .lock()
+            .unwrap()
+            .revoke_admin_sessions(&username, &session.actor())?,
+    ))
+}
+
+/// Starts (or restarts) enrollment. Codes are only required on login after one was confirmed.
+#[instrument(skip(state, payload), err)]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    session: Session,
+    ClientIp(ip): ClientIp,
+    payload: Option<Json<TotpReauthentication>>,
+) -> Result<Json<TotpEnrollment>, AppError> {
+    let actor = session.actor();
+    let username = session.admin.username;
+    reauthenticate_totp(&state, &username, ip, payload).await?;
+    let secret = totp::new_secret();
+    let recovery_codes = totp::new_recovery_codes();
+    let code_hashes: Vec<String> = recovery_codes.iter().map(|code| token_hash(code)).collect();
+    state
+        .db
+        .lock()
+        .unwrap()
+        .enroll_totp(&username, &secret, &code_hashes, &actor)?;
+    Ok(Json(TotpEnrollment {
+        otpauth_uri: totp::otpauth_uri(&username, &secret),
+        secret,
+        recovery_codes,
+    }))
+}
+
+#[instrument(skip(state, payload), err)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<TotpCode>,
+) -> Result<(), AppError> {
+    let actor = session.actor();
+    let username = session.admin.username;
+    let db = state.db.lock().unwrap();
+    let stored = db
+        .totp(&username)?
+        .ok_or_else(|| AppError::NotFound(format!("{username} didn't start enrolling TOTP")))?;
+    let step = totp::verify(&stored.secret, &payload.code, stored.last_step)?
+        .ok_or_else(|| AppError::Validation("Invalid TOTP code".to_owned()))?;
+    db.confirm_totp(&username, step, &actor)?;
+    Ok(())
+}
+
+#[instrument(skip(state, payload), err)]
+pub async fn remove_own_totp(
+    State(state): State<AppState>,
+    session: Session,
+    ClientIp(ip): ClientIp,
+    payload: Option<Json<TotpReauthentication>>,
+) -> Result<(), AppError> {
+    reauthenticate_totp(&state, &session.admin.username, ip, payload).await?;
+    state
+        .db
+        .lock()
+        .unwrap()
+        .remove_totp(&session.admin.username, &session.actor())?;
+    Ok(())
+}
+
+/// Checks the password or a current code if the admin has a confirmed enrollment,
+/// throttled like logins since it's as good as guessing them
+async fn reauthenticate_totp(
+    state: &AppState,
+    username: &str,
+    ip: IpAddr,
+    payload: Option<Json<TotpReauthentication>>,
+) -> Result<(), AppError> {
+    let stored = state.db.lock().unwrap().totp(username)?;
+    let Some(stored) = stored.filter(|stored| stored.confirmed) else {
+        return Ok(());
+    };
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let keys = [ThrottleKey::Ip(ip), ThrottleKey::account(username)];
+    let attempt = LoginAttempt::begin(&state.login_throttle, &keys)
+        .map_err(|retry_after| AppError::TooManyRequests { retry_after })?;
+    if let Some(code) = &payload.code {
+        if let Some(step) = totp::verify(&stored.secret, code, stored.last_step)? {
+            state.db.lock().unwrap().use_totp_step(username, step)?;
+            return Ok(());
+        }
+    }
+    if let Some(password) = &payload.password {
+        if verify_password(&state.db, username, password)
+            .await?
+            .is_some()
+        {
+            return Ok(());
+        }
+    }
+    attempt.failed();
+    Err(AppError::Unauthorized)
+}
+
+/// For admins who lost both their authenticator app and recovery codes
+#[instrument(skip(state), err)]
+pub async fn remove_totp(
+    State(state): State<AppState>,
+    session: Session,
+    Path(username): Path<String>,
+) -> Result<(), AppError> {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .remove_totp(&username, &session.actor())?;
+    Ok(())
+}
+
+#[instrument(err)]
+pub async fn auth(session: Session) -> Result<Json<AdminUser>, AppError> {
+    Ok(Json(session.admin))
+}
+
+/// The admin a request was made by, from its `Authorization: Bearer <token>` header
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub admin: AdminUser,
+    token_hash: String,
+    ip: IpAddr,
+}
+
+impl Session {
+    pub fn actor(&self) -> Actor {
+        Actor {
+            name: self.admin.username.clone(),
+            ip: Some(self.ip.to_string()),
+        }
+    }
+
+    /// Super admins have every role
+    pub fn has_role(&self, role: Role) -> bool {
+        self.admin.role == role || self.admin.role == Role::SuperAdmin
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Session {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, AppError> {
+        if let Some(session) = parts.extensions.remove::<Session>() {
+            return Ok(session);
+        }
+        let TypedHeader(authorization) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
+                .await
+                .map_err(|_| AppError::Unauthorized)?;
+        let token_hash = token_hash(authorization.token());
+        let admin = state
+            .db
+            .lock()
+            .unwrap()
+            .admin_session(&token_hash)?
+            .ok_or(AppError::Unauthorized)?;
+        let ClientIp(ip) = ClientIp::from_request_parts(parts, state).await?;
+        Ok(Self {
+            admin,
+            token_hash,
+            ip,
+        })
+    }
+}
+
+/// Who an anonymous suggestion or appeal is recorded under, refusing blocked and rate limited reporters
+async fn anonymous_actor(state: &AppState, ip: IpAddr) -> Result<Actor, AppError> {
+    let actor = Actor {
+        name: state.reporters.name(ip).await,
+        ip: Some(ip.to_string()),
+    };
+    if state
+        .db
+        .lock()
+        .unwrap()
+        .is_blocked(&actor.name, actor.ip.as_deref())?
+    {
+        tracing::warn!("Refused a suggestion from blocked reporter {}", actor.name);
+        return Err(AppError::Forbidden);
+    }
+    let keys = [
+        ThrottleKey::Ip(ip),
+        ThrottleKey::Reporter(actor.name.to_lowercase()),
+    ];
+    state
+        .suggestion_limiter
+        .lock()
+        .unwrap()
+        .try_hit(
+            &keys,
+            state.config.suggestion_limit,
+            state.config.suggestion_window,
+        )
+        .map_err(|retry_after| AppError::TooManyRequests { retry_after })?;
+    Ok(actor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_write_endpoint_is_guarded() {
+        let unguarded: Vec<String> = endpoints()
+            .into_iter()
+            .filter(|endpoint| endpoint.method != Method::GET && endpoint.access == Access::Public)
+            .map(|endpoint| format!("{} {}", endpoint.method, endpoint.path))
+            .collect();
+        assert!(
+            unguarded.is_empty(),
+            "Write endpoints must require a role, or be explicitly `Access::Anonymous`: {unguarded:?}"
+        );
     }
 }
@@ -1,35 +1,170 @@
-use anyhow::Result;
-use async_once_cell::OnceCell;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, ensure, Result};
+use password_hash::rand_core::{OsRng, RngCore};
+use password_hash::SaltString;
 use scrypt::{
-    password_hash::{PasswordHash, PasswordVerifier},
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier},
     Scrypt,
 };
+use sha2::{Digest, Sha256};
+
+use crate::error::DbError;
+use crate::hebrew_db::{Actor, HebrewDb};
+use crate::totp;
+use crate::types::{AdminUser, Role};
+
+/// How long a session token stays valid after logging in
+pub const SESSION_LIFETIME_HOURS: u32 = 12;
+
+/// Logs in with the `p.ass` password for as long as no admin user by this name was created
+const BOOTSTRAP_ADMIN: &str = "admin";
+
+const PASS_FILE: &str = "p.ass";
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+static PASS_CACHE: Mutex<Option<CachedPass>> = Mutex::new(None);
+
+struct CachedPass {
+    modified: SystemTime,
+    hash: String,
+}
+
+#[derive(Debug)]
+pub enum LoginOutcome {
+    Valid(AdminUser),
+    Invalid,
+    /// The password is valid, but the admin enrolled TOTP and no code was given
+    CodeRequired,
+}
+
+/// Checks the password, and for admins who enrolled TOTP, the code (or a recovery code)
+pub async fn authorize(
+    db: &Mutex<HebrewDb>,
+    username: &str,
+    password: &str,
+    code: Option<&str>,
+) -> Result<LoginOutcome> {
+    let Some(admin) = verify_password(db, username, password).await? else {
+        return Ok(LoginOutcome::Invalid);
+    };
+
+    let db = db.lock().unwrap();
+    let Some(stored) = db.totp(&admin.username)?.filter(|stored| stored.confirmed) else {
+        return Ok(LoginOutcome::Valid(admin));
+    };
+    let Some(code) = code else {
+        return Ok(LoginOutcome::CodeRequired);
+    };
+    if let Some(step) = totp::verify(&stored.secret, code, stored.last_step)? {
+        db.use_totp_step(&admin.username, step)?;
+        Ok(LoginOutcome::Valid(admin))
+    } else if db.use_recovery_code(&admin.username, &token_hash(code.trim()))? {
+        tracing::warn!("{} logged in with a recovery code", admin.username);
+        Ok(LoginOutcome::Valid(admin))
+    } else {
+        Ok(LoginOutcome::Invalid)
+    }
+}
 
-static CELL_HASH: OnceCell<PasswordHash<'static>> = OnceCell::new();
-static CELL_PASS: OnceCell<String> = OnceCell::new();
+/// Returns the admin the password belongs to, ignoring TOTP
+pub async fn verify_password(
+    db: &Mutex<HebrewDb>,
+    username: &str,
+    password: &str,
+) -> Result<Option<AdminUser>> {
+    let credentials = db.lock().unwrap().admin_credentials(username)?;
+    match credentials {
+        Some((hash, admin)) => Ok(verify(password, hash).await?.then_some(admin)),
+        None if username == BOOTSTRAP_ADMIN => Ok(verify(password, admin_hash().await?)
+            .await?
+            .then_some(AdminUser {
+                username: BOOTSTRAP_ADMIN.to_owned(),
+                role: Role::SuperAdmin,
+            })),
+        None => Ok(None),
+    }
+}
+
+/// Replaces the admin's password hash, in `p.ass` for the bootstrap admin,
+/// and logs them out of every other session
+pub async fn set_password(
+    db: &Mutex<HebrewDb>,
+    username: &str,
+    hash: &str,
+    kept_token_hash: &str,
+    actor: &Actor,
+) -> Result<()> {
+    let updated = db
+        .lock()
+        .unwrap()
+        .set_admin_password(username, hash, kept_token_hash, actor)?;
+    if !updated && username == BOOTSTRAP_ADMIN {
+        // Written in place rather than renamed over, since it may be a bind mount
+        tokio::fs::write(PASS_FILE, format!("{hash}\n")).await?;
+    }
+    Ok(())
+}
 
-pub async fn authorize(password: &str) -> Result<bool> {
+pub fn check_new_password(password: &str) -> Result<()> {
+    ensure!(
+        password.chars().count() >= MIN_PASSWORD_LENGTH,
+        DbError::Validation(format!(
+            "Passwords must be at least {MIN_PASSWORD_LENGTH} characters long"
+        ))
+    );
+    Ok(())
+}
+
+/// Hashes a password into a PHC string, as stored in `p.ass` and the admin users table
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
     Ok(Scrypt
-        .verify_password(password.as_bytes(), admin_hash().await?)
-        .is_ok())
-}
-
-async fn admin_hash() -> Result<&'static PasswordHash<'static>> {
-    CELL_HASH
-        .get_or_try_init(async {
-            let pass_string = admin_pass().await?;
-            let hash: PasswordHash<'static> = PasswordHash::new(pass_string)?;
-            Ok(hash)
-        })
-        .await
-}
-
-async fn admin_pass() -> Result<&'static str> {
-    CELL_PASS
-        .get_or_try_init(async {
-            let pass_string = tokio::fs::read_to_string("p.ass").await?;
-            Ok(pass_string)
-        })
-        .await
-        .map(|string| string.as_str())
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow!(err))?
+        .to_string())
+}
+
+/// A random session token, handed to the client once
+pub fn new_session_token() -> String {
+    let mut token = [0u8; 32];
+    OsRng.fill_bytes(&mut token);
+    hex::encode(token)
+}
+
+/// What gets stored in place of a session token.
+/// Tokens are random enough for a fast hash, unlike passwords.
+pub fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Runs on the blocking pool, since scrypt takes long enough to stall other requests
+async fn verify(password: &str, hash: String) -> Result<bool> {
+    let password = password.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash).map_err(|err| anyhow!(err))?;
+        Ok(Scrypt.verify_password(password.as_bytes(), &hash).is_ok())
+    })
+    .await?
+}
+
+/// The bootstrap admin's hash, reloaded whenever `p.ass` is modified
+async fn admin_hash() -> Result<String> {
+    let modified = tokio::fs::metadata(PASS_FILE).await?.modified()?;
+    if let Some(cached) = PASS_CACHE.lock().unwrap().as_ref() {
+        if cached.modified == modified {
+            return Ok(cached.hash.clone());
+        }
+    }
+    let hash = tokio::fs::read_to_string(PASS_FILE)
+        .await?
+        .trim()
+        .to_owned();
+    PasswordHash::new(&hash).map_err(|err| anyhow!("Invalid hash in {PASS_FILE}: {err}"))?;
+    *PASS_CACHE.lock().unwrap() = Some(CachedPass {
+        modified,
+        hash: hash.clone(),
+    });
+    Ok(hash)
 }
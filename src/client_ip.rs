@@ -0,0 +1,244 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::FORWARDED;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+
+use crate::api::AppState;
+use crate::config::ForwardedHeader;
+use crate::error::AppError;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_REAL_IP: &str = "x-real-ip";
+
+/// The address of the client a request came from, as reported by the trusted proxies in front of us
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, AppError> {
+        let ConnectInfo(peer) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Missing connection info")))?;
+        let config = state.config();
+        Ok(Self(client_ip(
+            &parts.headers,
+            peer.ip(),
+            &config.trusted_proxies,
+            config.forwarded_header,
+        )))
+    }
+}
+
+/// Walks the forwarding chain from the socket peer towards the client,
+/// stopping at the first address that isn't a trusted proxy.
+/// Only `header` is read, the others may have been sent by the client.
+pub fn client_ip(
+    headers: &HeaderMap,
+    peer: IpAddr,
+    trusted: &[IpNet],
+    header: ForwardedHeader,
+) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|network| network.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+    let chain = match header {
+        ForwardedHeader::Forwarded => forwarded_chain(headers),
+        ForwardedHeader::XForwardedFor => {
+            header_values(headers, X_FORWARDED_FOR).map(|values| split_list(&values))
+        }
+        ForwardedHeader::XRealIp => header_values(headers, X_REAL_IP).map(|value| vec![value]),
+    }
+    .unwrap_or_default();
+
+    let mut client = peer;
+    for node in chain.iter().rev() {
+        // Unknown or obfuscated addresses can't be traced any further
+        let Some(ip) = parse_node(node) else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(&ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// The `for=` addresses of an RFC 7239 `Forwarded` header, closest to the client first
+fn forwarded_chain(headers: &HeaderMap) -> Option<Vec<String>> {
+    let values = header_values(headers, FORWARDED.as_str())?;
+    Some(
+        split_list(&values)
+            .into_iter()
+            .map(|element| {
+                element
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                    .map(|(_, value)| value.trim().trim_matches('"').to_owned())
+                    .unwrap_or_default()
+            })
+            .collect(),
+    )
+}
+
+/// Every occurrence of a header joined into one comma-separated list, as proxies may append either way
+fn header_values(headers: &HeaderMap, name: &str) -> Option<String> {
+    let values: Vec<&str> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(|item| item.trim().to_owned()).collect()
+}
+
+/// An address, possibly bracketed and with a port: `192.0.2.1:80`, `[2001:db8::1]:80`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+    if let Some(bracketed) = node.strip_prefix('[') {
+        return bracketed.split_once(']')?.0.parse().ok();
+    }
+    node.parse::<SocketAddr>().ok().map(|address| address.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxies() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()]
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn untrusted_peers_are_the_client() {
+        let headers = headers(&[(X_FORWARDED_FOR, "192.0.2.1")]);
+        let client = client_ip(
+            &headers,
+            ip("198.51.100.7"),
+            &proxies(),
+            ForwardedHeader::XForwardedFor,
+        );
+        assert_eq!(client, ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn trusted_peers_forward_for_the_client() {
+        let headers = headers(&[(X_FORWARDED_FOR, "192.0.2.1")]);
+        let client = client_ip(
+            &headers,
+            ip("10.0.0.2"),
+            &proxies(),
+            ForwardedHeader::XForwardedFor,
+        );
+        assert_eq!(client, ip("192.0.2.1"));
+    }
+
+    #[test]
+    fn trusted_peers_without_the_header_are_the_client() {
+        let client = client_ip(
+            &HeaderMap::new(),
+            ip("10.0.0.2"),
+            &proxies(),
+            ForwardedHeader::XForwardedFor,
+        );
+        assert_eq!(client, ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn chains_stop_at_the_first_untrusted_address() {
+        // The client made up the first address, and the proxies appended the rest
+        let headers = headers(&[
+            (X_FORWARDED_FOR, "203.0.113.9, 192.0.2.1"),
+            (X_FORWARDED_FOR, "10.0.0.3"),
+        ]);
+        let client = client_ip(
+            &headers,
+            ip("10.0.0.2"),
+            &proxies(),
+            ForwardedHeader::XForwardedFor,
+        );
+        assert_eq!(client, ip("192.0.2.1"));
+    }
+
+    #[test]
+    fn only_the_configured_header_is_read() {
+        let headers = headers(&[
+            ("forwarded", "for=203.0.113.9"),
+            (X_REAL_IP, "203.0.113.10"),
+            (X_FORWARDED_FOR, "192.0.2.1"),
+        ]);
+        let peer = ip("10.0.0.2");
+        assert_eq!(
+            client_ip(&headers, peer, &proxies(), ForwardedHeader::XForwardedFor),
+            ip("192.0.2.1")
+        );
+        assert_eq!(
+            client_ip(&headers, peer, &proxies(), ForwardedHeader::Forwarded),
+            ip("203.0.113.9")
+        );
+        assert_eq!(
+            client_ip(&headers, peer, &proxies(), ForwardedHeader::XRealIp),
+            ip("203.0.113.10")
+        );
+    }
+
+    #[test]
+    fn forwarded_chains_read_the_for_parameters() {
+        let headers = headers(&[(
+            "forwarded",
+            r#"for=192.0.2.1;proto=https, For="[2001:db8::7]:4711";by=10.0.0.3"#,
+        )]);
+        let client = client_ip(&headers, ip("::1"), &proxies(), ForwardedHeader::Forwarded);
+        assert_eq!(client, ip("2001:db8::7"));
+    }
+
+    #[test]
+    fn obfuscated_nodes_stop_the_chain() {
+        let headers = headers(&[("forwarded", "for=192.0.2.1, for=_hidden, for=10.0.0.3")]);
+        let client = client_ip(
+            &headers,
+            ip("10.0.0.2"),
+            &proxies(),
+            ForwardedHeader::Forwarded,
+        );
+        assert_eq!(client, ip("10.0.0.3"));
+    }
+
+    #[test]
+    fn nodes_may_have_ports_and_brackets() {
+        assert_eq!(parse_node("192.0.2.1"), Some(ip("192.0.2.1")));
+        assert_eq!(parse_node("192.0.2.1:8080"), Some(ip("192.0.2.1")));
+        assert_eq!(parse_node("2001:db8::1"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("[2001:db8::1]"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("[2001:db8::1]:443"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node(""), None);
+    }
+}
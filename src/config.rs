@@ -0,0 +1,179 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use ipnet::IpNet;
+
+/// Settings read from `HEBREW_WEEK_*` environment variables, falling back to defaults
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Suggestions allowed per IP and per reporter within `suggestion_window`
+    pub suggestion_limit: u32,
+    pub suggestion_window: Duration,
+    /// The same mistake suggested again for a participant within this window is merged
+    /// into the pending suggestion as a witness, or refused if by the same reporter
+    pub duplicate_window: Duration,
+    /// Proxies whose forwarding headers are believed, as comma-separated CIDRs or addresses
+    pub trusted_proxies: Vec<IpNet>,
+    /// The only header the trusted proxies' chain is read from, since any other
+    /// may be passed through from the client unchanged
+    pub forwarded_header: ForwardedHeader,
+    pub reporter_names: ReporterNames,
+    /// Mixed into hashed reporter names, so they can't be reversed by hashing every IP.
+    /// Required for hashed names.
+    pub reporter_salt: String,
+    pub dns_timeout: Duration,
+    /// How long reverse lookups (including failed ones) are cached
+    pub dns_cache_ttl: Duration,
+    pub auto_approval: AutoApproval,
+}
+
+/// When a mistake suggestion is accepted without waiting for a moderator.
+/// The suggested word must already be known, and the reporter must be either
+/// allowlisted or have a good enough record.
+#[derive(Debug, Clone)]
+pub struct AutoApproval {
+    /// Reporter names whose suggestions are always approved, only trustworthy because
+    /// hostnames are forward-confirmed
+    pub reporters: Vec<String>,
+    /// Disabled unless set
+    pub min_acceptance_rate: Option<f64>,
+    /// Suggestions a reporter must have had moderated before their acceptance rate counts
+    pub min_moderated: u32,
+    /// Distinct reporters after which a suggestion is approved, disabled unless set
+    pub witness_quorum: Option<u32>,
+}
+
+/// The header trusted proxies record the addresses they forwarded for in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwardedHeader {
+    /// RFC 7239 `Forwarded`
+    Forwarded,
+    XForwardedFor,
+    /// A single address rather than a chain
+    XRealIp,
+}
+
+/// What anonymous reporters are shown as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReporterNames {
+    /// Their IP
+    Off,
+    /// The hostname their IP reverse resolves to if it resolves back to their IP, or their IP
+    Hostname,
+    /// A salted hash of their IP, for privacy
+    Hashed,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        let config = Self {
+            suggestion_limit: env_var("HEBREW_WEEK_SUGGESTION_LIMIT", 20)?,
+            suggestion_window: Duration::from_secs(env_var(
+                "HEBREW_WEEK_SUGGESTION_WINDOW_SECS",
+                60 * 60,
+            )?),
+            duplicate_window: Duration::from_secs(env_var(
+                "HEBREW_WEEK_DUPLICATE_WINDOW_SECS",
+                10 * 60,
+            )?),
+            trusted_proxies: networks(&env_var(
+                "HEBREW_WEEK_TRUSTED_PROXIES",
+                "127.0.0.0/8,::1/128".to_owned(),
+            )?)?,
+            forwarded_header: forwarded_header(&env_var(
+                "HEBREW_WEEK_FORWARDED_HEADER",
+                "x-forwarded-for".to_owned(),
+            )?)?,
+            reporter_names: reporter_names(&env_var(
+                "HEBREW_WEEK_REPORTER_NAMES",
+                "hostname".to_owned(),
+            )?)?,
+            reporter_salt: env_var("HEBREW_WEEK_REPORTER_SALT", String::new())?,
+            dns_timeout: Duration::from_millis(env_var("HEBREW_WEEK_DNS_TIMEOUT_MS", 500)?),
+            dns_cache_ttl: Duration::from_secs(env_var("HEBREW_WEEK_DNS_CACHE_SECS", 60 * 60)?),
+            auto_approval: AutoApproval {
+                reporters: env_var("HEBREW_WEEK_AUTO_APPROVE_REPORTERS", String::new())?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|reporter| !reporter.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+                min_acceptance_rate: optional_env_var("HEBREW_WEEK_AUTO_APPROVE_MIN_RATE")?,
+                min_moderated: env_var("HEBREW_WEEK_AUTO_APPROVE_MIN_MODERATED", 10)?,
+                witness_quorum: optional_env_var("HEBREW_WEEK_AUTO_APPROVE_WITNESSES")?,
+            },
+        };
+        ensure!(
+            config
+                .auto_approval
+                .min_acceptance_rate
+                .is_none_or(|rate| (0.0..=1.0).contains(&rate)),
+            "HEBREW_WEEK_AUTO_APPROVE_MIN_RATE must be between 0 and 1"
+        );
+        // Without a salt, hashes of every IPv4 address can be tried in minutes
+        ensure!(
+            config.reporter_names != ReporterNames::Hashed || !config.reporter_salt.is_empty(),
+            "HEBREW_WEEK_REPORTER_SALT must be set for hashed reporter names"
+        );
+        Ok(config)
+    }
+}
+
+fn env_var<T>(name: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    Ok(optional_env_var(name)?.unwrap_or(default))
+}
+
+fn optional_env_var<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .parse()
+                .with_context(|| format!("Invalid value {value:?} for {name}"))
+        })
+        .transpose()
+}
+
+fn networks(list: &str) -> Result<Vec<IpNet>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|network| !network.is_empty())
+        .map(|network| {
+            network
+                .parse()
+                .or_else(|_| network.parse::<std::net::IpAddr>().map(IpNet::from))
+                .with_context(|| format!("Invalid trusted proxy {network:?}"))
+        })
+        .collect()
+}
+
+fn forwarded_header(value: &str) -> Result<ForwardedHeader> {
+    Ok(match value.trim().to_lowercase().as_str() {
+        "forwarded" => ForwardedHeader::Forwarded,
+        "x-forwarded-for" => ForwardedHeader::XForwardedFor,
+        "x-real-ip" => ForwardedHeader::XRealIp,
+        _ => bail!(
+            "Invalid value {value:?} for HEBREW_WEEK_FORWARDED_HEADER, expected forwarded, x-forwarded-for or x-real-ip"
+        ),
+    })
+}
+
+fn reporter_names(value: &str) -> Result<ReporterNames> {
+    Ok(match value.trim().to_lowercase().as_str() {
+        "off" => ReporterNames::Off,
+        "hostname" => ReporterNames::Hostname,
+        "hashed" => ReporterNames::Hashed,
+        _ => bail!(
+            "Invalid value {value:?} for HEBREW_WEEK_REPORTER_NAMES, expected off, hostname or hashed"
+        ),
+    })
+}
@@ -1,37 +1,149 @@
 use std::fmt::Display;
+use std::time::Duration;
 
+use axum::http::header::RETRY_AFTER;
 use axum::response::{IntoResponse, Response};
+use axum::Json;
 use hyper::StatusCode;
+use rusqlite::ffi;
+use serde_derive::Serialize;
+use tsync::tsync;
 
 #[derive(Debug)]
 pub enum AppError {
-    Anyhow(anyhow::Error),
-    AuthError,
+    NotFound(String),
+    Conflict(String),
+    Validation(String),
+    Unauthorized,
+    /// Also an authorization failure, but the client should ask for a TOTP code
+    TotpRequired,
+    Forbidden,
+    TooManyRequests {
+        retry_after: Duration,
+    },
+    Internal(anyhow::Error),
+}
+
+/// Machine-readable kind of a failed request
+#[tsync]
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum ErrorCode {
+    NotFound,
+    Conflict,
+    Validation,
+    Unauthorized,
+    TotpRequired,
+    Forbidden,
+    TooManyRequests,
+    Internal,
+}
+
+/// The JSON body of every failed request
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// Failures caused by the request rather than by the server, raised by `HebrewDb` through `anyhow`
+#[derive(Debug)]
+pub enum DbError {
+    NotFound(String),
+    Conflict(String),
+    Validation(String),
+}
+
+impl AppError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::Conflict(_) => ErrorCode::Conflict,
+            Self::Validation(_) => ErrorCode::Validation,
+            Self::Unauthorized => ErrorCode::Unauthorized,
+            Self::TotpRequired => ErrorCode::TotpRequired,
+            Self::Forbidden => ErrorCode::Forbidden,
+            Self::TooManyRequests { .. } => ErrorCode::TooManyRequests,
+            Self::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// What the client is told, also used for each failed item of a batch
+    pub fn body(&self) -> ErrorBody {
+        let message = match self {
+            Self::Internal(err) => format!("Something went wrong: {}", err),
+            other => other.to_string(),
+        };
+        ErrorBody {
+            code: self.code(),
+            message,
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized | Self::TotpRequired => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
 }
 
 // Tell axum how to convert `AppError` into a response.
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        match self {
-            Self::Anyhow(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Something went wrong: {}", err),
-            )
-                .into_response(),
-            Self::AuthError => (StatusCode::UNAUTHORIZED).into_response(),
+        let mut response = (self.status(), Json(self.body())).into_response();
+        if let Self::TooManyRequests { retry_after } = self {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, retry_seconds(retry_after).into());
         }
+        response
     }
 }
 
+/// Rounded up, so clients never retry too early
+fn retry_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
 impl Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Anyhow(err) => write!(f, "{}", err),
-            Self::AuthError => write!(f, "AuthError"),
+            Self::NotFound(message) | Self::Conflict(message) | Self::Validation(message) => {
+                write!(f, "{}", message)
+            }
+            Self::Unauthorized => write!(f, "Unauthorized"),
+            Self::TotpRequired => write!(f, "A TOTP code is required"),
+            Self::Forbidden => write!(f, "Forbidden"),
+            Self::TooManyRequests { retry_after } => {
+                write!(
+                    f,
+                    "Too many attempts, retry in {}s",
+                    retry_seconds(*retry_after)
+                )
+            }
+            Self::Internal(err) => write!(f, "{}", err),
         }
     }
 }
 
+impl Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(message) | Self::Conflict(message) | Self::Validation(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
 // This enables using `?` on functions that return `Result<_, anyhow::Error>` to turn them into
 // `Result<_, AppError>`. That way you don't need to do that manually.
 impl<E> From<E> for AppError
@@ -39,6 +151,26 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self::Anyhow(err.into())
+        let err = err.into();
+        if let Some(db_error) = err.downcast_ref::<DbError>() {
+            return match db_error {
+                DbError::NotFound(message) => Self::NotFound(message.clone()),
+                DbError::Conflict(message) => Self::Conflict(message.clone()),
+                DbError::Validation(message) => Self::Validation(message.clone()),
+            };
+        }
+        match err.downcast_ref::<rusqlite::Error>() {
+            Some(rusqlite::Error::QueryReturnedNoRows) => Self::NotFound(err.to_string()),
+            Some(rusqlite::Error::SqliteFailure(failure, _)) => match failure.extended_code {
+                ffi::SQLITE_CONSTRAINT_UNIQUE | ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+                    Self::Conflict(format!("Already exists: {}", err))
+                }
+                ffi::SQLITE_CONSTRAINT_FOREIGNKEY => {
+                    Self::Conflict(format!("Still referenced: {}", err))
+                }
+                _ => Self::Internal(err),
+            },
+            _ => Self::Internal(err),
+        }
     }
 }
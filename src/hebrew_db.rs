@@ -1,17 +1,45 @@
 use anyhow::{anyhow, ensure, Context, Result};
 use itertools::Itertools;
-use rusqlite::{named_params, Connection, Error::QueryReturnedNoRows};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{named_params, Connection, Error::QueryReturnedNoRows, OptionalExtension, Row};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
+use crate::config::AutoApproval;
+use crate::error::DbError;
+use crate::migrations;
+use crate::totp::StoredTotp;
 use crate::types::{
-    CanonicalRequest, CountedMistake, DiscardMistakeSuggestion, MistakeReport, MistakeSuggestion,
-    PersonMistake, PersonMistakes, SuggestedMistake, SuggestedTranslation, Translation,
-    TranslationAddition, TranslationSuggestion,
+    AdminUser, Appeal, AppealDecision, AppealQuery, AppealStatus, AuditAction, AuditEntry,
+    AuditPage, AuditQuery, AutoApprovedMistake, BlockedReporter, CanonicalRequest,
+    CorrectionAction, CountedMistake, DiscardMistakeSuggestion, DiscardTranslationSuggestion,
+    EventStatus, LeaderboardEntry, LeaderboardQuery, LoggedMistake, MistakeCorrection,
+    MistakeCorrectionRecord, MistakeReport, MistakeSuggestion, NewAppeal, NewBlockedReporter,
+    NewWeekEvent, Participant, ParticipantMetadata, PersonMistake, PersonMistakes, ReporterStats,
+    Role, SuggestedMistake, SuggestedTranslation, SuggestionBatch, SuggestionDecision,
+    SuggestionFilter, SuggestionOutcome, Translation, TranslationAddition, TranslationSuggestion,
+    WeekEvent,
 };
 
 /// Represents a canonical representation (dictionary choice) that can be stored in "source-of-truth" tables
 #[derive(Debug)]
 struct CanonicalWord(pub String);
 
+const APPEALS_QUERY: &str = "SELECT Appeals.ROWID AS Id, Appeals.*, MistakesLog.* FROM Appeals
+     JOIN MistakesLog ON MistakesLog.ROWID = Appeals.ReportId";
+
+const DEFAULT_AUDIT_PAGE_SIZE: u32 = 50;
+const MAX_AUDIT_PAGE_SIZE: u32 = 500;
+
+/// Who made a change, as recorded in the audit log
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub name: String,
+    pub ip: Option<String>,
+}
+
 #[derive(Debug)]
 pub(crate) struct HebrewDb(Connection);
 
@@ -19,36 +47,274 @@ impl HebrewDb {
     const DB_PATH: &'static str = "hebrew.db";
 
     pub fn new() -> Result<Self> {
-        let db = Connection::open(Self::DB_PATH).context("Failed to connect to db")?;
-        let hebrew_db = HebrewDb(db);
-        hebrew_db.create_tables()?;
+        Self::open(Connection::open(Self::DB_PATH).context("Failed to connect to db")?)
+    }
+
+    /// A fresh database, gone once dropped
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self> {
+        Self::open(Connection::open_in_memory()?)
+    }
+
+    /// Migrates the database to the latest schema
+    fn open(db: Connection) -> Result<Self> {
+        migrations::migrate(&db)?;
+        // Can't be changed inside a transaction, so only enforced once migrations are done
+        db.pragma_update(None, "foreign_keys", true)?;
+
+        Ok(HebrewDb(db))
+    }
+
+    pub fn schema_version(&self) -> Result<u32> {
+        migrations::schema_version(&self.0)
+    }
+
+    pub fn events(&self) -> Result<Vec<WeekEvent>> {
+        self.0
+            .prepare("SELECT ROWID,* FROM Events ORDER BY StartDate DESC")?
+            .query_map([], event_from_row)?
+            .try_collect()
+            .map_err(|err| err.into())
+    }
 
-        Ok(hebrew_db)
+    pub fn event(&self, id: i64) -> Result<WeekEvent> {
+        self.0
+            .prepare("SELECT ROWID,* FROM Events WHERE ROWID = :id")?
+            .query_row([id], event_from_row)
+            .optional()?
+            .ok_or_else(|| unknown_event_err(id).into())
+    }
+
+    pub fn active_event(&self) -> Result<WeekEvent> {
+        self.event(self.active_event_id()?)
+    }
+
+    pub fn add_event(&self, event: NewWeekEvent, actor: &Actor) -> Result<WeekEvent> {
+        check_date_range(&self.0, Some(&event.start_date), Some(&event.end_date))?;
+        self.atomically(|| {
+            let params = named_params! {
+                ":name": event.name,
+                ":start": event.start_date,
+                ":end": event.end_date,
+                ":status": EventStatus::Planned,
+            };
+            let id = self
+                .0
+                .prepare("INSERT INTO Events VALUES(:name, :start, :end, :status)")?
+                .insert(params)?;
+            let added = self.event(id)?;
+            self.audit(actor, AuditAction::AddEvent, None::<()>, Some(&added))?;
+            Ok(added)
+        })
+    }
+
+    /// Activating an event finishes the previously active one, so there is at most one active event
+    pub fn set_event_status(
+        &self,
+        id: i64,
+        status: EventStatus,
+        actor: &Actor,
+    ) -> Result<WeekEvent> {
+        self.atomically(|| {
+            let before = self.event(id)?;
+            if status == EventStatus::Active {
+                self.0
+                    .prepare("UPDATE Events SET Status = :finished WHERE Status = :active")?
+                    .execute(named_params! {
+                        ":finished": EventStatus::Finished,
+                        ":active": EventStatus::Active,
+                    })?;
+            }
+            let rows_changed = self
+                .0
+                .prepare("UPDATE Events SET Status = :status WHERE ROWID = :id")?
+                .execute(named_params! {":status": status, ":id": id})?;
+            ensure!(rows_changed == 1, unknown_event_err(id));
+            let after = self.event(id)?;
+            self.audit(
+                actor,
+                AuditAction::SetEventStatus,
+                Some(&before),
+                Some(&after),
+            )?;
+            Ok(after)
+        })
     }
 
     pub fn is_known_word(&self, word: &str) -> Result<bool> {
         Ok(self.canonicalize(word)?.is_some())
     }
 
-    pub fn participants(&self) -> Result<Vec<String>> {
+    pub fn participants(&self, event: Option<i64>) -> Result<Vec<String>> {
+        let event = self.resolve_event(event)?;
         Ok(self
             .0
-            .prepare("SELECT Name FROM Participants")?
-            .query_map([], |row| row.get("Name"))?
+            .prepare("SELECT Name FROM Participants WHERE EventId = :event")?
+            .query_map([event], |row| row.get("Name"))?
             .collect::<Result<Vec<_>, _>>()?)
     }
 
-    pub fn add_participant(&self, name: &str) -> Result<()> {
+    pub fn add_participant(&self, name: &str, actor: &Actor) -> Result<()> {
+        let event = self.active_event_id()?;
+        self.atomically(|| {
+            self.0
+                .prepare(
+                    "INSERT INTO Participants (EventId, Name, Active) VALUES(:event, :name, TRUE)",
+                )?
+                .insert(named_params! {":event": event, ":name": name})?;
+            let added = self.participant(name, Some(event))?;
+            self.audit(actor, AuditAction::AddParticipant, None::<()>, Some(&added))
+        })
+    }
+
+    pub fn participant(&self, name: &str, event: Option<i64>) -> Result<Participant> {
+        let event = self.resolve_event(event)?;
         self.0
-            .prepare("INSERT INTO Participants VALUES(:name)")?
-            .insert([name])?;
-        Ok(())
+            .prepare("SELECT * FROM Participants WHERE EventId = :event AND Name = :name")?
+            .query_row(named_params! {":event": event, ":name": name}, |row| {
+                Ok(Participant {
+                    name: row.get("Name")?,
+                    display_name: row.get("DisplayName")?,
+                    team: row.get("Team")?,
+                    avatar_url: row.get("AvatarUrl")?,
+                    active: row.get("Active")?,
+                })
+            })
+            .optional()?
+            .ok_or_else(|| unknown_participant_err(name).into())
     }
 
-    pub fn all_mistakes(&self) -> Result<Vec<PersonMistakes>> {
-        let mut statement = self.0.prepare("SELECT * FROM Mistakes ORDER BY Name")?;
+    pub fn update_participant(
+        &self,
+        name: &str,
+        metadata: ParticipantMetadata,
+        event: Option<i64>,
+        actor: &Actor,
+    ) -> Result<Participant> {
+        let event = self.resolve_event(event)?;
+        self.atomically(|| {
+            let before = self.participant(name, Some(event))?;
+            self.0
+                .prepare(
+                    "UPDATE Participants
+                     SET DisplayName = :display_name, Team = :team, AvatarUrl = :avatar_url, Active = :active
+                     WHERE EventId = :event AND Name = :name",
+                )?
+                .execute(named_params! {
+                    ":display_name": metadata.display_name,
+                    ":team": metadata.team,
+                    ":avatar_url": metadata.avatar_url,
+                    ":active": metadata.active,
+                    ":event": event,
+                    ":name": name,
+                })?;
+            let after = self.participant(name, Some(event))?;
+            self.audit(actor, AuditAction::UpdateParticipant, Some(&before), Some(&after))?;
+            Ok(after)
+        })
+    }
+
+    /// Renames a participant along with all of their mistakes and suggestions in the event
+    pub fn rename_participant(
+        &self,
+        name: &str,
+        new_name: &str,
+        event: Option<i64>,
+        actor: &Actor,
+    ) -> Result<Participant> {
+        let event = self.resolve_event(event)?;
+        self.atomically(|| {
+            let before = self.participant(name, Some(event))?;
+            ensure!(
+                !before.name.eq_ignore_ascii_case(new_name),
+                DbError::Validation(format!(
+                    "{new_name} is the same name as {}, names are case-insensitive",
+                    before.name
+                ))
+            );
+            self.0
+                .prepare("UPDATE Participants SET Name = :new_name WHERE EventId = :event AND Name = :name")?
+                .execute(named_params! {":new_name": new_name, ":event": event, ":name": name})?;
+            self.move_participant_records(name, new_name, event)?;
+            let after = self.participant(new_name, Some(event))?;
+            self.audit(actor, AuditAction::RenameParticipant, Some(&before), Some(&after))?;
+            Ok(after)
+        })
+    }
+
+    /// Merges a duplicate participant into another one, moving all of their mistakes and suggestions
+    pub fn merge_participant(
+        &self,
+        name: &str,
+        into: &str,
+        event: Option<i64>,
+        actor: &Actor,
+    ) -> Result<Participant> {
+        let event = self.resolve_event(event)?;
+        self.atomically(|| {
+            let target = self.participant(into, Some(event))?;
+            let merged = self.participant(name, Some(event))?;
+            // Names are case-insensitive, so both may resolve to the same participant
+            ensure!(
+                !target.name.eq_ignore_ascii_case(&merged.name),
+                DbError::Validation(format!("Can't merge {name} into itself"))
+            );
+            self.move_participant_records(name, &target.name, event)?;
+            self.remove_participant_row(name, event)?;
+            self.audit(
+                actor,
+                AuditAction::MergeParticipant,
+                Some(&merged),
+                Some(&target),
+            )?;
+            Ok(target)
+        })
+    }
+
+    /// Only participants without recorded mistakes can be removed, others should be merged or deactivated
+    pub fn remove_participant(&self, name: &str, event: Option<i64>, actor: &Actor) -> Result<()> {
+        let event = self.resolve_event(event)?;
+        let reports: i64 = self.0.query_row(
+            "SELECT COUNT(*) FROM MistakesLog WHERE EventId = :event AND Name = :name",
+            named_params! {":event": event, ":name": name},
+            |row| row.get(0),
+        )?;
+        ensure!(
+            reports == 0,
+            DbError::Conflict(format!(
+                "{name} has recorded mistakes, merge or deactivate them instead"
+            ))
+        );
+        let suggested = self
+            .0
+            .prepare("SELECT 1 FROM MistakesSuggestions WHERE EventId = :event AND Name = :name")?
+            .exists(named_params! {":event": event, ":name": name})?;
+        ensure!(
+            !suggested,
+            DbError::Conflict(format!(
+                "{name} has pending mistake suggestions, moderate them or merge {name} instead"
+            ))
+        );
+        self.atomically(|| {
+            let removed = self.participant(name, Some(event))?;
+            self.remove_participant_row(name, event)?;
+            self.audit(
+                actor,
+                AuditAction::RemoveParticipant,
+                Some(&removed),
+                None::<()>,
+            )
+        })
+    }
+
+    pub fn all_mistakes(&self, event: Option<i64>) -> Result<Vec<PersonMistakes>> {
+        let event = self.resolve_event(event)?;
+        let mut statement = self.0.prepare(
+            "SELECT Name, Mistake, COUNT(*) AS Count FROM MistakesLog
+                 WHERE EventId = :event AND NOT Retracted GROUP BY Name, Mistake ORDER BY Name",
+        )?;
         let mistakes_group = statement
-            .query_map([], |row| {
+            .query_map([event], |row| {
                 Ok(PersonMistake {
                     name: row.get("Name")?,
                     counted_mistake: CountedMistake {
@@ -70,12 +336,14 @@ impl HebrewDb {
         Ok(mistakes)
     }
 
-    pub fn mistakes(&self, name: &str) -> Result<PersonMistakes> {
-        let mut statement = self
-            .0
-            .prepare("SELECT * FROM Mistakes WHERE Name = :name")?;
+    pub fn mistakes(&self, name: &str, event: Option<i64>) -> Result<PersonMistakes> {
+        let event = self.resolve_event(event)?;
+        let mut statement = self.0.prepare(
+            "SELECT Mistake, COUNT(*) AS Count FROM MistakesLog
+                 WHERE EventId = :event AND Name = :name AND NOT Retracted GROUP BY Mistake",
+        )?;
         let mistakes = statement
-            .query_map(named_params! {":name": name}, |row| {
+            .query_map(named_params! {":event": event, ":name": name}, |row| {
                 Ok(CountedMistake {
                     mistake: row.get("Mistake")?,
                     count: row.get("Count")?,
@@ -88,41 +356,652 @@ impl HebrewDb {
         })
     }
 
-    pub fn report_mistake(&self, report: MistakeReport) -> Result<PersonMistake> {
-        let name = &report.name;
-        let mistake = self.canonicalize(&report.mistake)?;
+    /// Ranks every participant of the event by their total mistakes within the given dates
+    pub fn leaderboard(&self, query: LeaderboardQuery) -> Result<Vec<LeaderboardEntry>> {
+        let event = self.resolve_event(query.event)?;
+        check_date_range(&self.0, query.from.as_deref(), query.to.as_deref())?;
+        let mut counts: BTreeMap<String, Vec<CountedMistake>> = self
+            .participants(Some(event))?
+            .into_iter()
+            .map(|name| (name, vec![]))
+            .collect();
+        let mut statement = self.0.prepare(
+            // Reported names may differ in case, so they're counted under the participant's
+            "SELECT Participants.Name AS Name, Mistake, COUNT(*) AS Count FROM MistakesLog
+             JOIN Participants USING (EventId, Name)
+             WHERE EventId = :event AND NOT Retracted
+             AND (:from IS NULL OR date(Timestamp) >= :from)
+             AND (:to IS NULL OR date(Timestamp) <= :to)
+             GROUP BY Participants.Name, Mistake",
+        )?;
+        let mut rows = statement.query(named_params! {
+            ":event": event,
+            ":from": query.from,
+            ":to": query.to,
+        })?;
+        while let Some(row) = rows.next()? {
+            counts
+                .entry(row.get("Name")?)
+                .or_default()
+                .push(CountedMistake {
+                    mistake: row.get("Mistake")?,
+                    count: row.get("Count")?,
+                });
+        }
+
+        let mut leaderboard = counts
+            .into_iter()
+            .map(|(name, mistakes)| LeaderboardEntry {
+                rank: 0,
+                name,
+                total: mistakes.iter().map(|mistake| mistake.count).sum(),
+                distinct_words: mistakes.len() as u32,
+                top_word: mistakes
+                    .iter()
+                    .max_by(|a, b| a.count.cmp(&b.count).then(b.mistake.cmp(&a.mistake)))
+                    .map(|mistake| mistake.mistake.clone()),
+            })
+            .collect_vec();
+        leaderboard.sort_by(|a, b| b.total.cmp(&a.total).then(a.name.cmp(&b.name)));
+        for index in 0..leaderboard.len() {
+            leaderboard[index].rank = match index {
+                0 => 1,
+                _ if leaderboard[index].total == leaderboard[index - 1].total => {
+                    leaderboard[index - 1].rank
+                }
+                _ => index as u32 + 1,
+            };
+        }
+        Ok(leaderboard)
+    }
+
+    /// Lists the individual reports of a participant's mistakes, optionally only of a single word
+    pub fn mistakes_log(
+        &self,
+        name: &str,
+        word: Option<&str>,
+        event: Option<i64>,
+    ) -> Result<Vec<LoggedMistake>> {
+        let event = self.resolve_event(event)?;
+        let mistake = match word {
+            Some(word) => match self.canonicalize(word)? {
+                Some(canonical) => Some(canonical.0),
+                None => return Ok(vec![]),
+            },
+            None => None,
+        };
+        self.0
+            .prepare(
+                "SELECT ROWID,* FROM MistakesLog
+                 WHERE EventId = :event AND Name = :name AND (:mistake IS NULL OR Mistake = :mistake)
+                 ORDER BY Timestamp, ROWID",
+            )?
+            .query_map(
+                named_params! {":event": event, ":name": name, ":mistake": mistake},
+                logged_mistake_from_row,
+            )?
+            .try_collect()
+            .map_err(|err| err.into())
+    }
+
+    pub fn report_mistake(&self, report: MistakeReport, actor: &Actor) -> Result<PersonMistake> {
+        let event = self.active_event_id()?;
+        let name = self.participant(&report.name, Some(event))?.name;
+        let mistake = self
+            .canonicalize(&report.mistake)?
+            .ok_or_else(|| unknown_word_err(&report.mistake))?;
+
+        self.atomically(|| {
+            let counted = self.report_mistake_canonical(
+                event,
+                mistake,
+                &name,
+                &actor.name,
+                report.context.as_deref(),
+            )?;
+            let logged = self.logged_mistake(self.0.last_insert_rowid())?;
+            self.audit(actor, AuditAction::ReportMistake, None::<()>, Some(&logged))?;
+            Ok(counted)
+        })
+    }
+
+    /// Retracts a single report, keeping it in the log but no longer counting it
+    pub fn retract_mistake(&self, report_id: i64, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            let report = self.logged_mistake(report_id)?;
+            ensure!(
+                !report.retracted,
+                DbError::Conflict(format!("Mistake report {report_id} was already retracted"))
+            );
+            self.0
+                .prepare("UPDATE MistakesLog SET Retracted = TRUE WHERE ROWID = :id")?
+                .execute([report_id])?;
+            self.record_correction(
+                &report,
+                CorrectionAction::Retracted,
+                None,
+                None,
+                &actor.name,
+            )?;
+            let after = self.logged_mistake(report_id)?;
+            self.audit(
+                actor,
+                AuditAction::RetractMistake,
+                Some(&report),
+                Some(&after),
+            )
+        })
+    }
+
+    /// Retracts the latest report of the mistake in the active event, undoing a single `report_mistake`
+    pub fn decrement_mistake(&self, report: MistakeReport, actor: &Actor) -> Result<PersonMistake> {
+        let mistake = self
+            .canonicalize(&report.mistake)?
+            .ok_or_else(|| unknown_word_err(&report.mistake))?;
+        let event = self.active_event_id()?;
+        let report_id: i64 = self
+            .0
+            .prepare(
+                "SELECT ROWID FROM MistakesLog
+                 WHERE EventId = :event AND Name = :name AND Mistake = :mistake AND NOT Retracted
+                 ORDER BY Timestamp DESC, ROWID DESC LIMIT 1",
+            )?
+            .query_row(
+                named_params! {":event": event, ":name": report.name, ":mistake": mistake.0},
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| {
+                DbError::NotFound(format!(
+                    "{} has no reports of {} to retract",
+                    report.name, mistake.0
+                ))
+            })?;
+        self.retract_mistake(report_id, actor)?;
+        Ok(PersonMistake {
+            counted_mistake: CountedMistake {
+                count: self.count_mistake(event, &report.name, &mistake.0)?,
+                mistake: mistake.0,
+            },
+            name: report.name,
+        })
+    }
+
+    /// Reassigns a report to a different participant and/or changes the reported word
+    pub fn correct_mistake(
+        &self,
+        report_id: i64,
+        correction: MistakeCorrection,
+        actor: &Actor,
+    ) -> Result<LoggedMistake> {
+        self.atomically(|| {
+            let report = self.logged_mistake(report_id)?;
+            ensure!(
+                !report.retracted,
+                DbError::Conflict(format!("Mistake report {report_id} was retracted"))
+            );
+            let name = match correction.name {
+                Some(name) => self.participant(&name, Some(report.event))?.name,
+                None => report.name.clone(),
+            };
+            let mistake = match correction.mistake {
+                Some(word) => {
+                    self.canonicalize(&word)?
+                        .ok_or_else(|| unknown_word_err(&word))?
+                        .0
+                }
+                None => report.mistake.clone(),
+            };
+            ensure!(
+                name != report.name || mistake != report.mistake,
+                DbError::Validation("The correction doesn't change anything".to_owned())
+            );
+            self.0
+                .prepare(
+                    "UPDATE MistakesLog SET Name = :name, Mistake = :mistake WHERE ROWID = :id",
+                )?
+                .execute(named_params! {":name": name, ":mistake": mistake, ":id": report_id})?;
+            self.record_correction(
+                &report,
+                CorrectionAction::Corrected,
+                Some(&name),
+                Some(&mistake),
+                &actor.name,
+            )?;
+            let after = self.logged_mistake(report_id)?;
+            self.audit(
+                actor,
+                AuditAction::CorrectMistake,
+                Some(&report),
+                Some(&after),
+            )?;
+            Ok(after)
+        })
+    }
+
+    pub fn mistake_corrections(&self, event: Option<i64>) -> Result<Vec<MistakeCorrectionRecord>> {
+        let event = self.resolve_event(event)?;
+        self.0
+            .prepare(
+                "SELECT MistakesCorrections.ROWID,MistakesCorrections.* FROM MistakesCorrections
+                 JOIN MistakesLog ON MistakesLog.ROWID = ReportId
+                 WHERE EventId = :event ORDER BY MistakesCorrections.Timestamp",
+            )?
+            .query_map([event], mistake_correction_from_row)?
+            .try_collect()
+            .map_err(|err| err.into())
+    }
+
+    /// A report may only be appealed once, while it is counted
+    pub fn appeal_mistake(
+        &self,
+        report_id: i64,
+        appeal: &NewAppeal,
+        actor: &Actor,
+    ) -> Result<Appeal> {
+        ensure!(
+            !appeal.reason.trim().is_empty(),
+            DbError::Validation("Appeals must give a reason".to_owned())
+        );
+        self.atomically(|| {
+            let report = self.logged_mistake(report_id)?;
+            ensure!(
+                !report.retracted,
+                DbError::Conflict(format!("Mistake report {report_id} was already retracted"))
+            );
+            // Upheld appeals don't block another, or anyone could block the participant's own
+            let appealed = self
+                .0
+                .prepare("SELECT 1 FROM Appeals WHERE ReportId = :report AND Status != :upheld")?
+                .exists(named_params! {":report": report_id, ":upheld": AppealStatus::Upheld})?;
+            ensure!(
+                !appealed,
+                DbError::Conflict(format!(
+                    "Mistake report {report_id} already has a pending or overturned appeal"
+                ))
+            );
+            let id = self
+                .0
+                .prepare(
+                    "INSERT INTO Appeals VALUES(:report, :reason, :appellant, :ip, :status,
+                     NULL, NULL, datetime('now'), NULL)",
+                )?
+                .insert(named_params! {
+                    ":report": report_id,
+                    ":reason": appeal.reason.trim(),
+                    ":appellant": actor.name,
+                    ":ip": actor.ip,
+                    ":status": AppealStatus::Pending,
+                })?;
+            let appeal = self.appeal(id)?;
+            self.audit(actor, AuditAction::AppealMistake, None::<()>, Some(&appeal))?;
+            Ok(appeal)
+        })
+    }
+
+    /// Oldest first, so the queue is handled in order
+    pub fn appeals(&self, query: AppealQuery) -> Result<Vec<Appeal>> {
+        let event = self.resolve_event(query.event)?;
+        self.0
+            .prepare(&format!(
+                "{APPEALS_QUERY} WHERE EventId = :event AND (:status IS NULL OR Status = :status)
+                 ORDER BY CreatedAt, Id"
+            ))?
+            .query_map(
+                named_params! {":event": event, ":status": query.status},
+                appeal_from_row,
+            )?
+            .try_collect()
+            .map_err(|err| err.into())
+    }
+
+    /// Overturning retracts the report, which keeps it in the mistakes log and its corrections
+    pub fn decide_appeal(
+        &self,
+        id: i64,
+        decision: AppealDecision,
+        actor: &Actor,
+    ) -> Result<Appeal> {
+        self.atomically(|| {
+            let before = self.appeal(id)?;
+            ensure!(
+                before.status == AppealStatus::Pending,
+                DbError::Conflict(format!("Appeal {id} was already decided"))
+            );
+            let (status, action) = if decision.overturn {
+                if !before.report.retracted {
+                    self.retract_mistake(before.report.id, actor)?;
+                }
+                (AppealStatus::Overturned, AuditAction::OverturnAppeal)
+            } else {
+                (AppealStatus::Upheld, AuditAction::UpholdAppeal)
+            };
+            self.0
+                .prepare(
+                    "UPDATE Appeals SET Status = :status, Moderator = :moderator,
+                     Response = :response, DecidedAt = datetime('now') WHERE ROWID = :id",
+                )?
+                .execute(named_params! {
+                    ":status": status,
+                    ":moderator": actor.name,
+                    ":response": decision.response,
+                    ":id": id,
+                })?;
+            let after = self.appeal(id)?;
+            self.audit(actor, action, Some(&before), Some(&after))?;
+            Ok(after)
+        })
+    }
+
+    fn appeal(&self, id: i64) -> Result<Appeal> {
+        self.0
+            .prepare(&format!("{APPEALS_QUERY} WHERE Appeals.ROWID = :id"))?
+            .query_row([id], appeal_from_row)
+            .optional()?
+            .ok_or_else(|| DbError::NotFound(format!("There is no appeal with id {id}")).into())
+    }
+
+    /// The same mistake suggested for a participant within `duplicate_window` of a pending one
+    /// is merged into it as a witness, unless by one of its witnesses or already auto-approved.
+    /// Suggestions matching `auto_approval` are accepted right away.
+    pub fn suggest_mistake(
+        &self,
+        mut suggestion: SuggestedMistake,
+        duplicate_window: Duration,
+        auto_approval: &AutoApproval,
+        actor: &Actor,
+    ) -> Result<SuggestionOutcome> {
+        let event = self.active_event_id()?;
+        suggestion.mistake.name = self
+            .participant(&suggestion.mistake.name, Some(event))?
+            .name;
+        self.atomically(|| {
+            if let Some((id, approved)) =
+                self.identical_suggestion(event, &suggestion, duplicate_window)?
+            {
+                ensure!(!approved, already_suggested_err(&suggestion));
+                return self.witness_mistake_suggestion(id, &suggestion, auto_approval, actor);
+            }
+            if let Some(mistake) = self.auto_approves(&suggestion, auto_approval)? {
+                return self.auto_approve_mistake(event, mistake, suggestion, actor);
+            }
+            let params = named_params! {
+                ":event": event,
+                ":name": suggestion.mistake.name,
+                ":mistake": suggestion.mistake.mistake,
+                ":context": suggestion.mistake.context,
+                ":reporter": suggestion.reporter,
+                ":ip": actor.ip,
+            };
+            suggestion.mistake.id = self
+                .0
+                .prepare(
+                    "INSERT INTO MistakesSuggestions
+                     VALUES(:event, :name, :mistake, :context, :reporter, datetime('now'), :ip)",
+                )?
+                .insert(params)?;
+            self.add_witness(suggestion.mistake.id, &suggestion.reporter, actor)?;
+            self.audit(
+                actor,
+                AuditAction::SuggestMistake,
+                None::<()>,
+                Some(&suggestion),
+            )?;
+            Ok(SuggestionOutcome {
+                id: suggestion.mistake.id,
+                auto_approved: false,
+                witnesses: 1,
+            })
+        })
+    }
+
+    /// A suggestion of the same participant and (canonical) mistake within `window`,
+    /// and whether it was already auto-approved rather than pending
+    fn identical_suggestion(
+        &self,
+        event: i64,
+        suggestion: &SuggestedMistake,
+        window: Duration,
+    ) -> Result<Option<(i64, bool)>> {
+        let candidates: Vec<(i64, String, bool)> = self
+            .0
+            .prepare(
+                "SELECT ROWID, Mistake, FALSE AS Approved FROM MistakesSuggestions
+                 WHERE EventId = :event AND Name = :name AND Timestamp > datetime('now', :window)
+                 UNION ALL
+                 SELECT ROWID, Mistake, TRUE FROM MistakesSuggestionsArchive
+                 WHERE EventId = :event AND Name = :name AND Timestamp > datetime('now', :window)
+                 AND Decision = 'Auto'",
+            )?
+            .query_map(
+                named_params! {
+                    ":event": event,
+                    ":name": suggestion.mistake.name,
+                    ":window": format!("-{} seconds", window.as_secs()),
+                },
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?
+            .try_collect()?;
+        let mistake = self.mistake_key(&suggestion.mistake.mistake)?;
+        for (id, candidate, approved) in candidates {
+            if self.mistake_key(&candidate)? == mistake {
+                return Ok(Some((id, approved)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// What identical mistakes are compared by: their canonical word, if known
+    fn mistake_key(&self, mistake: &str) -> Result<String> {
+        Ok(match self.canonicalize(mistake)? {
+            Some(canonical) => canonical.0,
+            None => mistake.trim().to_lowercase(),
+        })
+    }
+
+    fn witness_mistake_suggestion(
+        &self,
+        id: i64,
+        suggestion: &SuggestedMistake,
+        auto_approval: &AutoApproval,
+        actor: &Actor,
+    ) -> Result<SuggestionOutcome> {
+        let mut witnesses = self.witnesses(id)?;
+        ensure!(
+            !witnesses
+                .iter()
+                .any(|witness| witness.eq_ignore_ascii_case(&suggestion.reporter)),
+            already_suggested_err(suggestion)
+        );
+        self.add_witness(id, &suggestion.reporter, actor)?;
+        witnesses.push(suggestion.reporter.clone());
+        self.audit(
+            actor,
+            AuditAction::WitnessMistakeSuggestion,
+            None::<()>,
+            Some(json!({ "suggestion": id, "witness": suggestion, "witnesses": witnesses })),
+        )?;
 
-        match mistake {
-            Some(mistake) => self.report_mistake_canonical(mistake, name),
-            None => Err(unknown_word_err(&report.mistake)),
+        let witness_count = witnesses.len() as u32;
+        let quorum = auto_approval
+            .witness_quorum
+            .is_some_and(|quorum| witness_count >= quorum);
+        let known = self.canonicalize(&suggestion.mistake.mistake)?.is_some();
+        if quorum && known {
+            self.decide_mistake_suggestion(id, SuggestionDecision::Auto, actor)?;
         }
+        Ok(SuggestionOutcome {
+            id,
+            auto_approved: quorum && known,
+            witnesses: witness_count,
+        })
     }
 
-    pub fn suggest_mistake(&self, suggestion: SuggestedMistake) -> Result<i64> {
+    fn add_witness(&self, id: i64, reporter: &str, actor: &Actor) -> Result<()> {
         self.0
             .prepare(
-                "INSERT INTO MistakesSuggestions VALUES(:name, :mistake, :context, :reporter)",
+                "INSERT INTO MistakesSuggestionsWitnesses
+                 VALUES(:id, :reporter, :ip, datetime('now'))",
             )?
-            .insert([
-                suggestion.mistake.name,
-                suggestion.mistake.mistake,
-                suggestion.mistake.context,
-                suggestion.reporter,
-            ])
+            .insert(named_params! {
+                ":id": id,
+                ":reporter": reporter,
+                ":ip": actor.ip,
+            })?;
+        Ok(())
+    }
+
+    /// Reporters of a pending suggestion, in the order they suggested it
+    fn witnesses(&self, id: i64) -> Result<Vec<String>> {
+        self.0
+            .prepare(
+                "SELECT Reporter FROM MistakesSuggestionsWitnesses
+                 WHERE SuggestionId = :id ORDER BY Timestamp, ROWID",
+            )?
+            .query_map([id], |row| row.get(0))?
+            .try_collect()
             .map_err(|err| err.into())
     }
 
-    pub fn suggest_translation(&self, suggestion: SuggestedTranslation) -> Result<i64> {
+    /// The canonical mistake to record if the suggestion is auto-approved
+    fn auto_approves(
+        &self,
+        suggestion: &SuggestedMistake,
+        auto_approval: &AutoApproval,
+    ) -> Result<Option<CanonicalWord>> {
+        let Some(mistake) = self.canonicalize(&suggestion.mistake.mistake)? else {
+            return Ok(None);
+        };
+        let allowlisted = auto_approval
+            .reporters
+            .iter()
+            .any(|reporter| reporter.eq_ignore_ascii_case(&suggestion.reporter));
+        let trusted = || -> Result<bool> {
+            let Some(min_rate) = auto_approval.min_acceptance_rate else {
+                return Ok(false);
+            };
+            Ok(self
+                .query_reporter_stats(Some(&suggestion.reporter))?
+                .first()
+                .filter(|stats| stats.accepted + stats.rejected >= auto_approval.min_moderated)
+                .and_then(|stats| stats.acceptance_rate)
+                .is_some_and(|rate| rate >= min_rate))
+        };
+        Ok((allowlisted || trusted()?).then_some(mistake))
+    }
+
+    fn auto_approve_mistake(
+        &self,
+        event: i64,
+        mistake: CanonicalWord,
+        suggestion: SuggestedMistake,
+        actor: &Actor,
+    ) -> Result<SuggestionOutcome> {
+        self.report_mistake_canonical(
+            event,
+            mistake,
+            &suggestion.mistake.name,
+            &suggestion.reporter,
+            Some(&suggestion.mistake.context),
+        )?;
+        let report_id = self.0.last_insert_rowid();
+        let id = self.archive_mistake_suggestion(
+            event,
+            &suggestion,
+            actor.ip.as_deref(),
+            SuggestionDecision::Auto,
+            Some(report_id),
+        )?;
+        let report = self.logged_mistake(report_id)?;
+        self.audit(
+            actor,
+            AuditAction::AutoApproveMistakeSuggestion,
+            Some(&suggestion),
+            Some(&report),
+        )?;
+        Ok(SuggestionOutcome {
+            id,
+            auto_approved: true,
+            witnesses: 1,
+        })
+    }
+
+    /// Mistake suggestions auto-approved in the event, including reverted ones, newest first
+    pub fn auto_approved_mistakes(&self, event: Option<i64>) -> Result<Vec<AutoApprovedMistake>> {
+        let event = self.resolve_event(event)?;
         self.0
-            .prepare("INSERT INTO TranslationsSuggestions VALUES(:english, :hebrew, :suggestor)")?
-            .insert([
-                suggestion.translation.english,
-                suggestion.translation.hebrew,
-                suggestion.suggestor,
-            ])
+            .prepare(
+                "SELECT ROWID,* FROM MistakesSuggestionsArchive
+                 WHERE EventId = :event AND Decision IN ('Auto', 'Reverted')
+                 ORDER BY Timestamp DESC, ROWID DESC",
+            )?
+            .query_map([event], auto_approved_mistake_from_row)?
+            .try_collect()
             .map_err(|err| err.into())
     }
 
+    /// Retracts the report an auto-approved suggestion was recorded as
+    pub fn revert_auto_approval(&self, id: i64, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            let approved = self
+                .0
+                .prepare(
+                    "SELECT ROWID,* FROM MistakesSuggestionsArchive
+                     WHERE ROWID = :id AND Decision = 'Auto'",
+                )?
+                .query_row([id], auto_approved_mistake_from_row)
+                .optional()?
+                .ok_or_else(|| {
+                    DbError::NotFound(format!("There is no auto-approved suggestion with id {id}"))
+                })?;
+            if !self.logged_mistake(approved.report_id)?.retracted {
+                self.retract_mistake(approved.report_id, actor)?;
+            }
+            self.0
+                .prepare(
+                    "UPDATE MistakesSuggestionsArchive
+                     SET Decision = :decision, Accepted = FALSE WHERE ROWID = :id",
+                )?
+                .execute(named_params! {
+                    ":decision": SuggestionDecision::Reverted,
+                    ":id": id,
+                })?;
+            self.audit(
+                actor,
+                AuditAction::RevertAutoApproval,
+                Some(&approved),
+                None::<()>,
+            )
+        })
+    }
+
+    pub fn suggest_translation(
+        &self,
+        mut suggestion: SuggestedTranslation,
+        actor: &Actor,
+    ) -> Result<i64> {
+        self.atomically(|| {
+            suggestion.translation.id = self
+                .0
+                .prepare(
+                    "INSERT INTO TranslationsSuggestions VALUES(:english, :hebrew, :suggestor, :ip)",
+                )?
+                .insert(named_params! {
+                    ":english": suggestion.translation.english,
+                    ":hebrew": suggestion.translation.hebrew,
+                    ":suggestor": suggestion.suggestor,
+                    ":ip": actor.ip,
+                })?;
+            self.audit(
+                actor,
+                AuditAction::SuggestTranslation,
+                None::<()>,
+                Some(&suggestion),
+            )?;
+            Ok(suggestion.translation.id)
+        })
+    }
+
     pub fn all_translations(&self) -> Result<Vec<Translation>> {
         let mut statement = self.0.prepare("SELECT * FROM Translations")?;
         let translations = statement
@@ -136,16 +1015,23 @@ impl HebrewDb {
         Ok(translations)
     }
 
-    pub fn add_translation(&self, translation: TranslationAddition) -> Result<()> {
-        let canonical = self.canonicalize(&translation.translation.english)?;
-        match canonical {
-            Some(canonical) => self.add_translation_canonical(
+    pub fn add_translation(&self, translation: TranslationAddition, actor: &Actor) -> Result<()> {
+        let canonical = self
+            .canonicalize(&translation.translation.english)?
+            .ok_or_else(|| unknown_word_err(&translation.translation.english))?;
+        self.atomically(|| {
+            self.add_translation_canonical(
                 canonical,
                 &translation.translation.hebrew,
                 &translation.suggestor,
-            ),
-            None => Err(unknown_word_err(&translation.translation.english)),
-        }
+            )?;
+            self.audit(
+                actor,
+                AuditAction::AddTranslation,
+                None::<()>,
+                Some(&translation),
+            )
+        })
     }
 
     pub fn translate(&self, english: &str) -> Result<Vec<String>> {
@@ -156,77 +1042,455 @@ impl HebrewDb {
         }
     }
 
-    pub fn discard_mistake_suggestion(&self, suggestion: DiscardMistakeSuggestion) -> Result<()> {
-        let suggested_mistake: SuggestedMistake = self
+    /// Archives a mistake suggestion, recording the suggested mistake if it was accepted
+    pub fn discard_mistake_suggestion(
+        &self,
+        suggestion: DiscardMistakeSuggestion,
+        actor: &Actor,
+    ) -> Result<Option<PersonMistake>> {
+        let decision = if suggestion.accepted {
+            SuggestionDecision::Accepted
+        } else {
+            SuggestionDecision::Rejected
+        };
+        self.atomically(|| self.decide_mistake_suggestion(suggestion.id, decision, actor))
+    }
+
+    fn decide_mistake_suggestion(
+        &self,
+        id: i64,
+        decision: SuggestionDecision,
+        actor: &Actor,
+    ) -> Result<Option<PersonMistake>> {
+        let witnesses = self.witnesses(id)?;
+        let (event, suggested_mistake, reporter_ip): (i64, SuggestedMistake, Option<String>) = self
             .0
             .prepare("SELECT * FROM MistakesSuggestions WHERE ROWID = :id")?
-            .query_row([suggestion.id], |row| {
-                Ok(SuggestedMistake {
-                    mistake: MistakeSuggestion {
-                        id: suggestion.id,
-                        name: row.get("Name")?,
-                        mistake: row.get("Mistake")?,
-                        context: row.get("Context")?,
+            .query_row([id], |row| {
+                Ok((
+                    row.get("EventId")?,
+                    SuggestedMistake {
+                        mistake: MistakeSuggestion {
+                            id,
+                            name: row.get("Name")?,
+                            mistake: row.get("Mistake")?,
+                            context: row.get("Context")?,
+                        },
+                        reporter: row.get("Reporter")?,
+                        reporter_stats: None,
+                        witnesses: (witnesses.len() > 1).then(|| witnesses.clone()),
                     },
-                    reporter: row.get("Reporter")?,
-                })
+                    row.get("ReporterIp")?,
+                ))
+            })
+            .optional()?
+            .ok_or_else(|| {
+                DbError::NotFound(format!("There is no mistake suggestion with id {id}"))
             })?;
         ensure!(
             self.0
                 .prepare("DELETE FROM MistakesSuggestions WHERE ROWID = :id")?
-                .execute([suggestion.id])?
+                .execute([id])?
                 == 1,
-            format!(
-                "Failed to delete mistake suggestion with id {}",
-                suggestion.id
-            )
+            format!("Failed to delete mistake suggestion with id {id}")
         );
+        self.0
+            .prepare("DELETE FROM MistakesSuggestionsWitnesses WHERE SuggestionId = :id")?
+            .execute([id])?;
+        let recorded = if decision != SuggestionDecision::Rejected {
+            let mistake = self
+                .canonicalize(&suggested_mistake.mistake.mistake)?
+                .ok_or_else(|| unknown_word_err(&suggested_mistake.mistake.mistake))?;
+            let recorded = self.report_mistake_canonical(
+                event,
+                mistake,
+                &suggested_mistake.mistake.name,
+                &suggested_mistake.reporter,
+                Some(&suggested_mistake.mistake.context),
+            )?;
+            Some((recorded, self.0.last_insert_rowid()))
+        } else {
+            None
+        };
+        let action = match decision {
+            SuggestionDecision::Auto => AuditAction::AutoApproveMistakeSuggestion,
+            SuggestionDecision::Rejected => AuditAction::RejectMistakeSuggestion,
+            _ => AuditAction::AcceptMistakeSuggestion,
+        };
+        let after = match &recorded {
+            Some((_, report_id)) => Some(self.logged_mistake(*report_id)?),
+            None => None,
+        };
+        self.audit(actor, action, Some(&suggested_mistake), after.as_ref())?;
+        self.archive_mistake_suggestion(
+            event,
+            &suggested_mistake,
+            reporter_ip.as_deref(),
+            decision,
+            recorded.as_ref().map(|(_, report_id)| *report_id),
+        )?;
+        Ok(recorded.map(|(recorded, _)| recorded))
+    }
+
+    fn archive_mistake_suggestion(
+        &self,
+        event: i64,
+        suggestion: &SuggestedMistake,
+        reporter_ip: Option<&str>,
+        decision: SuggestionDecision,
+        report_id: Option<i64>,
+    ) -> Result<i64> {
         let params = named_params! {
-            ":name": suggested_mistake.mistake.name,
-             ":mistake":suggested_mistake.mistake.mistake,
-             ":context":suggested_mistake.mistake.context,
-             ":reporter": suggested_mistake.reporter,
-             ":accepted": suggestion.accepted
+            ":event": event,
+            ":name": suggestion.mistake.name,
+            ":mistake": suggestion.mistake.mistake,
+            ":context": suggestion.mistake.context,
+            ":reporter": suggestion.reporter,
+            ":accepted": decision != SuggestionDecision::Rejected,
+            ":ip": reporter_ip,
+            ":decision": decision,
+            ":report_id": report_id,
+        };
+        Ok(self
+            .0
+            .prepare(
+                "INSERT INTO MistakesSuggestionsArchive
+                 VALUES(:event, :name, :mistake, :context, :reporter, :accepted, :ip,
+                 :decision, :report_id, datetime('now'))",
+            )?
+            .insert(params)?)
+    }
+
+    /// Archives a translation suggestion, promoting it into `Translations` if it was accepted
+    pub fn discard_translation_suggestion(
+        &self,
+        suggestion: DiscardTranslationSuggestion,
+        actor: &Actor,
+    ) -> Result<()> {
+        self.atomically(|| self.discard_translation_suggestion_imp(suggestion, actor))
+    }
+
+    /// Accepts or rejects a batch of mistake suggestions of the event in one transaction.
+    /// Each is decided on its own, so one failing is reported and left pending
+    /// without holding back the rest.
+    pub fn moderate_mistake_suggestions(
+        &self,
+        batch: &SuggestionBatch,
+        actor: &Actor,
+    ) -> Result<Vec<(i64, Result<()>)>> {
+        let decision = if batch.accepted {
+            SuggestionDecision::Accepted
+        } else {
+            SuggestionDecision::Rejected
+        };
+        let event = self.resolve_event(batch.event)?;
+        self.moderate_batch(
+            batch,
+            || {
+                Ok(self
+                    .all_mistake_suggestions(batch.event, &batch.filter, true)?
+                    .into_iter()
+                    .map(|suggestion| suggestion.mistake.id)
+                    .collect())
+            },
+            |id| {
+                let suggested_in: Option<i64> = self
+                    .0
+                    .prepare("SELECT EventId FROM MistakesSuggestions WHERE ROWID = :id")?
+                    .query_row([id], |row| row.get(0))
+                    .optional()?;
+                ensure!(
+                    suggested_in.is_none_or(|suggested_in| suggested_in == event),
+                    DbError::Validation(format!("Suggestion {id} isn't in event {event}"))
+                );
+                self.decide_mistake_suggestion(id, decision, actor)?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Accepts (as suggested) or rejects a batch of translation suggestions in one transaction,
+    /// like `moderate_mistake_suggestions`
+    pub fn moderate_translation_suggestions(
+        &self,
+        batch: &SuggestionBatch,
+        actor: &Actor,
+    ) -> Result<Vec<(i64, Result<()>)>> {
+        self.moderate_batch(
+            batch,
+            || {
+                Ok(self
+                    .all_translation_suggestions(&batch.filter)?
+                    .into_iter()
+                    .map(|suggestion| suggestion.translation.id)
+                    .collect())
+            },
+            |id| {
+                self.discard_translation_suggestion_imp(
+                    DiscardTranslationSuggestion {
+                        id,
+                        accepted: batch.accepted,
+                        hebrew: None,
+                    },
+                    actor,
+                )
+            },
+        )
+    }
+
+    /// Decides every selected suggestion in its own savepoint, within one for the whole batch.
+    /// Ids given along with a filter must match it too.
+    fn moderate_batch(
+        &self,
+        batch: &SuggestionBatch,
+        matching: impl FnOnce() -> Result<Vec<i64>>,
+        decide: impl Fn(i64) -> Result<()>,
+    ) -> Result<Vec<(i64, Result<()>)>> {
+        let filtered = batch.filter.reporter.is_some()
+            || batch.filter.participant.is_some()
+            || batch.filter.word.is_some();
+        ensure!(
+            filtered || batch.ids.is_some(),
+            DbError::Validation("Select the batch by ids, a filter or both".to_owned())
+        );
+        self.atomically(|| {
+            let matching = if filtered { Some(matching()?) } else { None };
+            let ids = match (&batch.ids, &matching) {
+                (Some(ids), _) => ids.iter().copied().unique().collect(),
+                (None, Some(matching)) => matching.clone(),
+                (None, None) => vec![],
+            };
+            Ok(ids
+                .into_iter()
+                .map(|id| {
+                    let result = if matching
+                        .as_ref()
+                        .is_some_and(|matching| !matching.contains(&id))
+                    {
+                        Err(DbError::Validation(format!(
+                            "Suggestion {id} doesn't match the filter"
+                        ))
+                        .into())
+                    } else {
+                        self.atomically(|| decide(id))
+                    };
+                    (id, result)
+                })
+                .collect())
+        })
+    }
+
+    fn discard_translation_suggestion_imp(
+        &self,
+        suggestion: DiscardTranslationSuggestion,
+        actor: &Actor,
+    ) -> Result<()> {
+        let suggestion_id = suggestion.id;
+        let (mut suggested_translation, suggestor_ip): (SuggestedTranslation, Option<String>) =
+            self.0
+                .prepare("SELECT * FROM TranslationsSuggestions WHERE ROWID = :id")?
+                .query_row([suggestion_id], |row| {
+                    Ok((
+                        SuggestedTranslation {
+                            translation: TranslationSuggestion {
+                                id: suggestion_id,
+                                english: row.get("English")?,
+                                hebrew: row.get("Hebrew")?,
+                            },
+                            suggestor: row.get("Suggestor")?,
+                        },
+                        row.get("SuggestorIp")?,
+                    ))
+                })
+                .optional()?
+                .ok_or_else(|| {
+                    DbError::NotFound(format!(
+                        "There is no translation suggestion with id {suggestion_id}"
+                    ))
+                })?;
+        let before = json!(suggested_translation);
+        ensure!(
+            self.0
+                .prepare("DELETE FROM TranslationsSuggestions WHERE ROWID = :id")?
+                .execute([suggestion_id])?
+                == 1,
+            format!("Failed to delete translation suggestion with id {suggestion_id}")
+        );
+        if let Some(hebrew) = suggestion.hebrew {
+            suggested_translation.translation.hebrew = hebrew;
+        }
+        if suggestion.accepted {
+            let english = &suggested_translation.translation.english;
+            let canonical = self
+                .canonicalize(english)?
+                .ok_or_else(|| unknown_word_err(english))?;
+            self.add_translation_canonical(
+                canonical,
+                &suggested_translation.translation.hebrew,
+                &suggested_translation.suggestor,
+            )?;
+        }
+        let params = named_params! {
+            ":english": suggested_translation.translation.english,
+            ":hebrew": suggested_translation.translation.hebrew,
+            ":suggestor": suggested_translation.suggestor,
+            ":accepted": suggestion.accepted,
+            ":moderator": actor.name,
+            ":ip": suggestor_ip,
         };
         self.0
-            .prepare("INSERT INTO MistakesSuggestionsArchive VALUES(:name, :mistake, :context, :reporter, :accepted)")?
+            .prepare(
+                "INSERT INTO TranslationsSuggestionsArchive
+                 VALUES(:english, :hebrew, :suggestor, :accepted, :moderator, datetime('now'), :ip)",
+            )?
             .insert(params)?;
-        Ok(())
+        if suggestion.accepted {
+            self.audit(
+                actor,
+                AuditAction::AcceptTranslationSuggestion,
+                Some(&before),
+                Some(&suggested_translation),
+            )
+        } else {
+            self.audit(
+                actor,
+                AuditAction::RejectTranslationSuggestion,
+                Some(&before),
+                None::<()>,
+            )
+        }
     }
 
-    pub fn discard_translation_suggestion(&self, suggestion_id: i64) -> Result<()> {
-        ensure!(
+    /// Reporter stats and witnesses are only filled in (and matched) with `details`, for moderators
+    pub fn all_mistake_suggestions(
+        &self,
+        event: Option<i64>,
+        filter: &SuggestionFilter,
+        details: bool,
+    ) -> Result<Vec<SuggestedMistake>> {
+        let event = self.resolve_event(event)?;
+        let mut stats: HashMap<String, ReporterStats> = HashMap::new();
+        let mut witnesses: HashMap<i64, Vec<String>> = HashMap::new();
+        if details {
+            stats = self
+                .reporter_stats()?
+                .into_iter()
+                .map(|stats| (stats.reporter.clone(), stats))
+                .collect();
             self.0
-                .prepare("DELETE FROM TranslationsSuggestions WHERE ROWID = :id")?
-                .execute([suggestion_id])?
-                == 1,
-            format!("Failed to delete mistake suggestion with id {suggestion_id}")
-        );
-        Ok(())
-    }
-
-    pub fn all_mistake_suggestions(&self) -> Result<Vec<SuggestedMistake>> {
-        self.0
-            .prepare("SELECT ROWID,* FROM MistakesSuggestions")?
-            .query_map([], |row| {
+                .prepare(
+                    "SELECT SuggestionId, Reporter FROM MistakesSuggestionsWitnesses
+                 ORDER BY Timestamp, ROWID",
+                )?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .try_for_each(|witness| -> Result<()> {
+                    let (id, reporter) = witness?;
+                    witnesses.entry(id).or_default().push(reporter);
+                    Ok(())
+                })?;
+        }
+        let mut suggestions: Vec<SuggestedMistake> = self
+            .0
+            .prepare("SELECT ROWID,* FROM MistakesSuggestions WHERE EventId = :event")?
+            .query_map([event], |row| {
+                let id = row.get("ROWID")?;
+                let reporter: String = row.get("Reporter")?;
                 Ok(SuggestedMistake {
                     mistake: MistakeSuggestion {
-                        id: row.get("ROWID")?,
+                        id,
                         name: row.get("Name")?,
                         mistake: row.get("Mistake")?,
                         context: row.get("Context")?,
                     },
+                    reporter_stats: stats.get(&reporter).cloned(),
+                    witnesses: details.then(|| witnesses.remove(&id).unwrap_or_default()),
+                    reporter,
+                })
+            })?
+            .try_collect()?;
+        if let Some(name) = &filter.participant {
+            let participant = self.participant(name, Some(event))?;
+            suggestions.retain(|suggestion| suggestion.mistake.name == participant.name);
+        }
+        if let Some(reporter) = &filter.reporter {
+            let reporter = reporter.trim().to_lowercase();
+            suggestions.retain(|suggestion| {
+                suggestion
+                    .witnesses
+                    .iter()
+                    .flatten()
+                    .chain([&suggestion.reporter])
+                    .any(|witness| witness.to_lowercase() == reporter)
+            });
+        }
+        if let Some(word) = &filter.word {
+            let word = self.mistake_key(word)?;
+            let keys: Vec<String> = suggestions
+                .iter()
+                .map(|suggestion| self.mistake_key(&suggestion.mistake.mistake))
+                .try_collect()?;
+            let mut keys = keys.into_iter();
+            suggestions.retain(|_| keys.next().is_some_and(|key| key == word));
+        }
+        Ok(suggestions)
+    }
+
+    /// Every mistake reporter's record, most reliable first
+    pub fn reporter_stats(&self) -> Result<Vec<ReporterStats>> {
+        self.query_reporter_stats(None)
+    }
+
+    fn query_reporter_stats(&self, reporter: Option<&str>) -> Result<Vec<ReporterStats>> {
+        self.0
+            .prepare(
+                "SELECT Reporter, COUNT(*) AS Submitted,
+                 SUM(Decision IS 'Accepted') AS Accepted,
+                 SUM(Decision IS 'Rejected' OR Decision IS 'Reverted') AS Rejected,
+                 SUM(Decision IS NULL) AS Pending, SUM(Decision IS 'Auto') AS AutoApproved
+                 FROM (SELECT Reporter, Decision FROM MistakesSuggestionsArchive
+                       UNION ALL SELECT Reporter, NULL FROM MistakesSuggestions)
+                 WHERE :reporter IS NULL OR Reporter = :reporter
+                 GROUP BY Reporter
+                 ORDER BY CAST(Accepted AS REAL) / NULLIF(Accepted + Rejected, 0) DESC NULLS LAST,
+                 Submitted DESC",
+            )?
+            .query_map([reporter], |row| {
+                let accepted: u32 = row.get("Accepted")?;
+                let rejected: u32 = row.get("Rejected")?;
+                Ok(ReporterStats {
                     reporter: row.get("Reporter")?,
+                    submitted: row.get("Submitted")?,
+                    accepted,
+                    rejected,
+                    pending: row.get("Pending")?,
+                    auto_approved: row.get("AutoApproved")?,
+                    acceptance_rate: (accepted + rejected > 0)
+                        .then(|| accepted as f64 / (accepted + rejected) as f64),
                 })
             })?
             .try_collect()
             .map_err(|err| err.into())
     }
 
-    pub fn all_translation_suggestions(&self) -> Result<Vec<SuggestedTranslation>> {
+    pub fn all_translation_suggestions(
+        &self,
+        filter: &SuggestionFilter,
+    ) -> Result<Vec<SuggestedTranslation>> {
+        ensure!(
+            filter.participant.is_none(),
+            DbError::Validation("Translation suggestions have no participant".to_owned())
+        );
+        let params = named_params! {
+            ":suggestor": filter.reporter.as_deref().map(str::trim),
+            ":word": filter.word.as_deref().map(str::trim),
+        };
         self.0
-            .prepare("SELECT ROWID,* FROM TranslationsSuggestions")?
-            .query_map([], |row| {
+            .prepare(
+                "SELECT ROWID,* FROM TranslationsSuggestions
+                 WHERE (:suggestor IS NULL OR Suggestor = :suggestor COLLATE NOCASE)
+                 AND (:word IS NULL OR English = :word COLLATE NOCASE OR Hebrew = :word)",
+            )?
+            .query_map(params, |row| {
                 Ok(SuggestedTranslation {
                     translation: TranslationSuggestion {
                         id: row.get("ROWID")?,
@@ -240,15 +1504,28 @@ impl HebrewDb {
             .map_err(|err| err.into())
     }
 
-    pub fn add_canonical(&self, request: CanonicalRequest) -> Result<()> {
-        self.add_canonical_imp(&request)?;
+    pub fn add_canonical(&self, request: CanonicalRequest, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            let before = self
+                .canonicalize(&request.word)?
+                .map(|canonical| CanonicalRequest {
+                    word: request.word.clone(),
+                    canonical: canonical.0,
+                });
+            self.add_canonical_imp(&request)?;
 
-        let tertiary_request = CanonicalRequest {
-            word: request.canonical.clone(),
-            canonical: request.canonical.clone(),
-        };
-        self.add_canonical_imp(&tertiary_request)?;
-        Ok(())
+            let tertiary_request = CanonicalRequest {
+                word: request.canonical.clone(),
+                canonical: request.canonical.clone(),
+            };
+            self.add_canonical_imp(&tertiary_request)?;
+            self.audit(
+                actor,
+                AuditAction::AddCanonical,
+                before.as_ref(),
+                Some(&request),
+            )
+        })
     }
 
     fn add_canonical_imp(&self, request: &CanonicalRequest) -> Result<()> {
@@ -270,7 +1547,7 @@ impl HebrewDb {
         let canonical = self.canonicalize(word)?;
         match canonical {
             Some(w) => Ok(w.0),
-            None => Err(unknown_word_err(word)),
+            None => Err(unknown_word_err(word).into()),
         }
     }
 
@@ -288,122 +1565,533 @@ impl HebrewDb {
         }
     }
 
-    fn create_tables(&self) -> Result<()> {
-        Self::create_table(&self.0, "Participants", [("Name", DbFieldType::String)], [])?;
-        Self::create_table(
-            &self.0,
-            "Mistakes",
-            [
-                ("Name", DbFieldType::String),
-                ("Mistake", DbFieldType::String),
-            ],
-            [("Count", DbFieldType::Int)],
+    pub fn blocked_reporters(&self) -> Result<Vec<BlockedReporter>> {
+        self.0
+            .prepare("SELECT * FROM BlockedReporters ORDER BY Timestamp DESC")?
+            .query_map([], blocked_reporter_from_row)?
+            .try_collect()
+            .map_err(|err| err.into())
+    }
+
+    /// Whether suggestions by this reporter, or from this IP, are refused
+    pub fn is_blocked(&self, reporter: &str, ip: Option<&str>) -> Result<bool> {
+        Ok(self
+            .0
+            .prepare("SELECT 1 FROM BlockedReporters WHERE Reporter IN (:reporter, :ip)")?
+            .exists(named_params! {
+                ":reporter": reporter,
+                ":ip": ip,
+            })?)
+    }
+
+    pub fn block_reporter(&self, blocked: &NewBlockedReporter, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            self.0
+                .prepare(
+                    "INSERT INTO BlockedReporters
+                     VALUES(:reporter, :reason, :blocked_by, datetime('now'))",
+                )?
+                .insert(named_params! {
+                    ":reporter": blocked.reporter.trim(),
+                    ":reason": blocked.reason,
+                    ":blocked_by": actor.name,
+                })?;
+            self.audit(actor, AuditAction::BlockReporter, None::<()>, Some(blocked))
+        })
+    }
+
+    pub fn unblock_reporter(&self, reporter: &str, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            let blocked = self
+                .0
+                .prepare("DELETE FROM BlockedReporters WHERE Reporter = :reporter RETURNING *")?
+                .query_row([reporter], blocked_reporter_from_row)
+                .optional()?
+                .ok_or_else(|| DbError::NotFound(format!("{reporter} is not blocked!")))?;
+            self.audit(
+                actor,
+                AuditAction::UnblockReporter,
+                Some(&blocked),
+                None::<()>,
+            )
+        })
+    }
+
+    pub fn admin_users(&self) -> Result<Vec<AdminUser>> {
+        self.0
+            .prepare("SELECT Username, Role FROM AdminUsers ORDER BY Username")?
+            .query_map([], |row| {
+                Ok(AdminUser {
+                    username: row.get("Username")?,
+                    role: row.get("Role")?,
+                })
+            })?
+            .try_collect()
+            .map_err(|err| err.into())
+    }
+
+    /// The admin's password hash (PHC string) along with the admin
+    pub fn admin_credentials(&self, username: &str) -> Result<Option<(String, AdminUser)>> {
+        Ok(self
+            .0
+            .prepare("SELECT * FROM AdminUsers WHERE Username = :username")?
+            .query_row([username], |row| {
+                Ok((
+                    row.get("Hash")?,
+                    AdminUser {
+                        username: row.get("Username")?,
+                        role: row.get("Role")?,
+                    },
+                ))
+            })
+            .optional()?)
+    }
+
+    pub fn add_admin_user(&self, admin: &AdminUser, hash: &str, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            self.0
+                .prepare("INSERT INTO AdminUsers VALUES(:username, :hash, :role)")?
+                .insert(named_params! {
+                    ":username": admin.username,
+                    ":hash": hash,
+                    ":role": admin.role,
+                })?;
+            self.audit(actor, AuditAction::AddAdminUser, None::<()>, Some(admin))
+        })
+    }
+
+    pub fn remove_admin_user(&self, username: &str, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            let (_, admin) = self
+                .admin_credentials(username)?
+                .ok_or_else(|| DbError::NotFound(format!("{username} is an unknown admin!")))?;
+            self.0
+                .prepare("DELETE FROM AdminUsers WHERE Username = :username")?
+                .execute([username])?;
+            self.delete_admin_sessions(username, None)?;
+            self.delete_totp(username)?;
+            self.audit(
+                actor,
+                AuditAction::RemoveAdminUser,
+                Some(&admin),
+                None::<()>,
+            )
+        })
+    }
+
+    /// Replaces any previous enrollment with an unconfirmed one
+    pub fn enroll_totp(
+        &self,
+        username: &str,
+        secret: &str,
+        recovery_code_hashes: &[String],
+        actor: &Actor,
+    ) -> Result<()> {
+        self.atomically(|| {
+            self.delete_totp(username)?;
+            self.0
+                .prepare("INSERT INTO AdminTotp VALUES(:username, :secret, FALSE, NULL)")?
+                .insert(named_params! {":username": username, ":secret": secret})?;
+            let mut insert_code = self
+                .0
+                .prepare("INSERT INTO AdminRecoveryCodes VALUES(:username, :code_hash)")?;
+            for code_hash in recovery_code_hashes {
+                insert_code
+                    .insert(named_params! {":username": username, ":code_hash": code_hash})?;
+            }
+            self.audit(actor, AuditAction::EnrollTotp, None::<()>, Some(username))
+        })
+    }
+
+    pub fn totp(&self, username: &str) -> Result<Option<StoredTotp>> {
+        Ok(self
+            .0
+            .prepare("SELECT * FROM AdminTotp WHERE Username = :username")?
+            .query_row([username], |row| {
+                Ok(StoredTotp {
+                    secret: row.get("Secret")?,
+                    confirmed: row.get("Confirmed")?,
+                    last_step: row.get("LastStep")?,
+                })
+            })
+            .optional()?)
+    }
+
+    /// Marks the step as used, so its code can't be used again.
+    /// Part of logging in, which is audited as a whole.
+    pub fn use_totp_step(&self, username: &str, step: u64) -> Result<()> {
+        self.0
+            .prepare("UPDATE AdminTotp SET LastStep = :step WHERE Username = :username")?
+            .execute(named_params! {":username": username, ":step": step})?;
+        Ok(())
+    }
+
+    /// Makes codes required on login, once the admin proved their app generates them
+    pub fn confirm_totp(&self, username: &str, step: u64, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            self.0
+                .prepare(
+                    "UPDATE AdminTotp SET Confirmed = TRUE, LastStep = :step WHERE Username = :username",
+                )?
+                .execute(named_params! {":username": username, ":step": step})?;
+            self.audit(actor, AuditAction::ConfirmTotp, None::<()>, Some(username))
+        })
+    }
+
+    /// Consumes the recovery code, returning whether it was valid
+    pub fn use_recovery_code(&self, username: &str, code_hash: &str) -> Result<bool> {
+        let rows_changed = self
+            .0
+            .prepare(
+                "DELETE FROM AdminRecoveryCodes WHERE Username = :username AND CodeHash = :code_hash",
+            )?
+            .execute(named_params! {":username": username, ":code_hash": code_hash})?;
+        Ok(rows_changed == 1)
+    }
+
+    pub fn remove_totp(&self, username: &str, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            self.delete_totp(username)?;
+            self.audit(actor, AuditAction::RemoveTotp, Some(username), None::<()>)
+        })
+    }
+
+    /// Replaces the admin's hash (if they're in the database, unlike the bootstrap admin)
+    /// and logs them out of every session except `kept_token_hash`
+    pub fn set_admin_password(
+        &self,
+        username: &str,
+        hash: &str,
+        kept_token_hash: &str,
+        actor: &Actor,
+    ) -> Result<bool> {
+        self.atomically(|| {
+            let rows_changed = self
+                .0
+                .prepare("UPDATE AdminUsers SET Hash = :hash WHERE Username = :username")?
+                .execute(named_params! {":username": username, ":hash": hash})?;
+            self.delete_admin_sessions(username, Some(kept_token_hash))?;
+            self.audit(
+                actor,
+                AuditAction::ChangePassword,
+                None::<()>,
+                Some(username),
+            )?;
+            Ok(rows_changed == 1)
+        })
+    }
+
+    /// Stores a new session for `admin`, returning when it expires
+    pub fn add_admin_session(
+        &self,
+        token_hash: &str,
+        admin: &AdminUser,
+        lifetime_hours: u32,
+        actor: &Actor,
+    ) -> Result<String> {
+        self.atomically(|| {
+            self.0.execute(
+                "DELETE FROM AdminSessions WHERE ExpiresAt <= datetime('now')",
+                (),
+            )?;
+            let expires_at: String = self
+                .0
+                .prepare(
+                    "INSERT INTO AdminSessions
+                     VALUES(:token_hash, :username, :role, datetime('now'), datetime('now', :lifetime))
+                     RETURNING ExpiresAt",
+                )?
+                .query_row(
+                    named_params! {
+                        ":token_hash": token_hash,
+                        ":username": admin.username,
+                        ":role": admin.role,
+                        ":lifetime": format!("+{lifetime_hours} hours"),
+                    },
+                    |row| row.get(0),
+                )?;
+            self.audit(actor, AuditAction::Login, None::<()>, Some(admin))?;
+            Ok(expires_at)
+        })
+    }
+
+    /// The admin an unexpired session belongs to
+    pub fn admin_session(&self, token_hash: &str) -> Result<Option<AdminUser>> {
+        Ok(self
+            .0
+            .prepare(
+                "SELECT Username, Role FROM AdminSessions
+                 WHERE TokenHash = :token_hash AND ExpiresAt > datetime('now')",
+            )?
+            .query_row([token_hash], |row| {
+                Ok(AdminUser {
+                    username: row.get("Username")?,
+                    role: row.get("Role")?,
+                })
+            })
+            .optional()?)
+    }
+
+    pub fn remove_admin_session(&self, token_hash: &str, actor: &Actor) -> Result<()> {
+        self.atomically(|| {
+            self.0
+                .prepare("DELETE FROM AdminSessions WHERE TokenHash = :token_hash")?
+                .execute([token_hash])?;
+            self.audit(actor, AuditAction::Logout, None::<()>, None::<()>)
+        })
+    }
+
+    /// Logs the admin out everywhere, returning the number of sessions revoked
+    pub fn revoke_admin_sessions(&self, username: &str, actor: &Actor) -> Result<usize> {
+        self.atomically(|| {
+            let revoked = self.delete_admin_sessions(username, None)?;
+            self.audit(
+                actor,
+                AuditAction::RevokeSessions,
+                None::<()>,
+                Some(json!({"username": username, "revoked": revoked})),
+            )?;
+            Ok(revoked)
+        })
+    }
+
+    /// Newest entries first
+    pub fn audit_log(&self, query: AuditQuery) -> Result<AuditPage> {
+        check_date_range(&self.0, query.from.as_deref(), query.to.as_deref())?;
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query
+            .page_size
+            .unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)
+            .clamp(1, MAX_AUDIT_PAGE_SIZE);
+        let filter = "WHERE (:actor IS NULL OR Actor = :actor)
+             AND (:action IS NULL OR Action = :action)
+             AND (:from IS NULL OR date(Timestamp) >= :from)
+             AND (:to IS NULL OR date(Timestamp) <= :to)";
+        let filter_params = named_params! {
+            ":actor": query.actor,
+            ":action": query.action,
+            ":from": query.from,
+            ":to": query.to,
+        };
+        let total = self.0.query_row(
+            &format!("SELECT COUNT(*) FROM AuditLog {filter}"),
+            filter_params,
+            |row| row.get(0),
         )?;
-        Self::create_table(
-            &self.0,
+        let mut statement = self.0.prepare(&format!(
+            "SELECT ROWID,* FROM AuditLog {filter} ORDER BY ROWID DESC LIMIT :limit OFFSET :offset"
+        ))?;
+        // Can't overflow in i64, however far past the last page
+        let offset = (i64::from(page) - 1) * i64::from(page_size);
+        let params = [
+            filter_params,
+            named_params! {
+                ":limit": page_size,
+                ":offset": offset,
+            },
+        ]
+        .concat();
+        let entries = statement
+            .query_map(params.as_slice(), |row| {
+                Ok(AuditEntry {
+                    id: row.get("ROWID")?,
+                    actor: row.get("Actor")?,
+                    ip: row.get("Ip")?,
+                    action: row.get("Action")?,
+                    before: row.get("Before")?,
+                    after: row.get("After")?,
+                    timestamp: row.get("Timestamp")?,
+                })
+            })?
+            .try_collect()?;
+        Ok(AuditPage {
+            entries,
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    /// Appends to the audit log. Called by every method changing data, in the same savepoint as the change.
+    fn audit(
+        &self,
+        actor: &Actor,
+        action: AuditAction,
+        before: Option<impl Serialize>,
+        after: Option<impl Serialize>,
+    ) -> Result<()> {
+        self.0
+            .prepare(
+                "INSERT INTO AuditLog VALUES(:actor, :ip, :action, :before, :after, datetime('now'))",
+            )?
+            .insert(named_params! {
+                ":actor": actor.name,
+                ":ip": actor.ip,
+                ":action": action,
+                ":before": to_json(before),
+                ":after": to_json(after),
+            })?;
+        Ok(())
+    }
+
+    fn delete_totp(&self, username: &str) -> Result<()> {
+        self.0
+            .prepare("DELETE FROM AdminTotp WHERE Username = :username")?
+            .execute([username])?;
+        self.0
+            .prepare("DELETE FROM AdminRecoveryCodes WHERE Username = :username")?
+            .execute([username])?;
+        Ok(())
+    }
+
+    fn delete_admin_sessions(
+        &self,
+        username: &str,
+        kept_token_hash: Option<&str>,
+    ) -> Result<usize> {
+        Ok(self
+            .0
+            .prepare(
+                "DELETE FROM AdminSessions
+                 WHERE Username = :username AND (:kept IS NULL OR TokenHash != :kept)",
+            )?
+            .execute(named_params! {":username": username, ":kept": kept_token_hash})?)
+    }
+
+    /// Runs `action` inside a savepoint, so either all of its changes are kept or none are.
+    /// Savepoints nest, so atomic operations may be composed of other atomic operations.
+    fn atomically<T>(&self, action: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.0.execute_batch("SAVEPOINT atomically")?;
+        match action() {
+            Ok(value) => {
+                self.0.execute_batch("RELEASE atomically")?;
+                Ok(value)
+            }
+            Err(err) => {
+                self.0
+                    .execute_batch("ROLLBACK TO atomically; RELEASE atomically")?;
+                Err(err)
+            }
+        }
+    }
+
+    fn logged_mistake(&self, report_id: i64) -> Result<LoggedMistake> {
+        self.0
+            .prepare("SELECT ROWID,* FROM MistakesLog WHERE ROWID = :id")?
+            .query_row([report_id], logged_mistake_from_row)
+            .optional()?
+            .ok_or_else(|| {
+                DbError::NotFound(format!("There is no mistake report with id {report_id}")).into()
+            })
+    }
+
+    fn remove_participant_row(&self, name: &str, event: i64) -> Result<()> {
+        let rows_changed = self
+            .0
+            .prepare("DELETE FROM Participants WHERE EventId = :event AND Name = :name")?
+            .execute(named_params! {":event": event, ":name": name})?;
+        ensure!(rows_changed == 1, unknown_participant_err(name));
+        Ok(())
+    }
+
+    fn move_participant_records(&self, name: &str, new_name: &str, event: i64) -> Result<()> {
+        for table in [
+            "MistakesLog",
             "MistakesSuggestions",
-            [],
-            [
-                ("Name", DbFieldType::String),
-                ("Mistake", DbFieldType::String),
-                ("Context", DbFieldType::String),
-                ("Reporter", DbFieldType::String),
-            ],
-        )?;
-        Self::create_table(
-            &self.0,
             "MistakesSuggestionsArchive",
-            [],
-            [
-                ("Name", DbFieldType::String),
-                ("Mistake", DbFieldType::String),
-                ("Context", DbFieldType::String),
-                ("Reporter", DbFieldType::String),
-                ("Accepted", DbFieldType::Int),
-            ],
-        )?;
-        Self::create_table(
-            &self.0,
-            "Translations",
-            [
-                ("English", DbFieldType::String),
-                ("Hebrew", DbFieldType::String),
-            ],
-            [("Suggestor", DbFieldType::String)],
-        )?;
-        Self::create_table(
-            &self.0,
-            "TranslationsSuggestions",
-            [
-                ("English", DbFieldType::String),
-                ("Hebrew", DbFieldType::String),
-            ],
-            [("Suggestor", DbFieldType::String)],
-        )?;
-        Self::create_table(
-            &self.0,
-            "CanonicalWords",
-            [("Word", DbFieldType::String)],
-            [("Canonical", DbFieldType::String)],
-        )?;
+        ] {
+            self.0
+                .prepare(&format!(
+                    "UPDATE {table} SET Name = :new_name WHERE EventId = :event AND Name = :name"
+                ))?
+                .execute(named_params! {":new_name": new_name, ":event": event, ":name": name})?;
+        }
         Ok(())
     }
 
-    fn create_table<
-        IUnique: IntoIterator<Item = (&'static str, DbFieldType)> + Copy,
-        IOther: IntoIterator<Item = (&'static str, DbFieldType)>,
-    >(
-        db: &Connection,
-        table_name: &str,
-        unique_fields: IUnique,
-        other_fields: IOther,
+    fn count_mistake(&self, event: i64, name: &str, mistake: &str) -> Result<u32> {
+        Ok(self.0.query_row(
+            "SELECT COUNT(*) FROM MistakesLog
+             WHERE EventId = :event AND Name = :name AND Mistake = :mistake AND NOT Retracted",
+            named_params! {":event": event, ":name": name, ":mistake": mistake},
+            |row| row.get(0),
+        )?)
+    }
+
+    fn record_correction(
+        &self,
+        report: &LoggedMistake,
+        action: CorrectionAction,
+        new_name: Option<&str>,
+        new_mistake: Option<&str>,
+        moderator: &str,
     ) -> Result<()> {
-        let fields = unique_fields
-            .into_iter()
-            .chain(other_fields)
-            .map(|(name, field_type)| format!("{} {}", name, field_type.to_type_string()))
-            .collect::<Vec<String>>()
-            .join(",\n");
-        let constraint = if unique_fields.into_iter().peekable().peek().is_some() {
-            format!(
-                ", CONSTRAINT u UNIQUE({})",
-                unique_fields
-                    .into_iter()
-                    .map(|(name, _)| name.to_owned())
-                    .join(", ")
-            )
-        } else {
-            String::from("")
-        };
-        let table_query =
-            format!("CREATE TABLE IF NOT EXISTS {table_name} ({fields}{constraint});");
-        db.execute(&table_query, ())
-            .context(format!("Failed creating table {table_name}"))?;
+        self.0
+            .prepare(
+                "INSERT INTO MistakesCorrections VALUES(
+                 :report, :action, :old_name, :old_mistake, :new_name, :new_mistake, :moderator, datetime('now'))",
+            )?
+            .insert(named_params! {
+                ":report": report.id,
+                ":action": action,
+                ":old_name": report.name,
+                ":old_mistake": report.mistake,
+                ":new_name": new_name,
+                ":new_mistake": new_mistake,
+                ":moderator": moderator,
+            })?;
         Ok(())
     }
 
+    fn active_event_id(&self) -> Result<i64> {
+        self.0
+            .prepare("SELECT ROWID FROM Events WHERE Status = :active")?
+            .query_row([EventStatus::Active], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| DbError::NotFound("There is no active event!".to_owned()).into())
+    }
+
+    /// Resolves an explicitly requested event, falling back to the active one
+    fn resolve_event(&self, event: Option<i64>) -> Result<i64> {
+        match event {
+            Some(id) => Ok(self.event(id)?.id),
+            None => self.active_event_id(),
+        }
+    }
+
     fn report_mistake_canonical(
         &self,
+        event: i64,
         mistake: CanonicalWord,
         name: &str,
+        reporter: &str,
+        context: Option<&str>,
     ) -> Result<PersonMistake> {
         let mistake = mistake.0;
-        let params = named_params! {":name": name, ":mistake": mistake};
-
-        let mut upsert_statement = self.0.prepare(
-            "INSERT INTO Mistakes VALUES(:name, :mistake, 1)
-             ON CONFLICT(Name, Mistake) DO UPDATE SET Count = Count + 1",
-        )?;
 
-        let rows_changed = upsert_statement.execute(params)?;
+        let rows_changed = self
+            .0
+            .prepare(
+                "INSERT INTO MistakesLog
+                 VALUES(:event, :name, :mistake, :reporter, :context, datetime('now'), FALSE)",
+            )?
+            .execute(named_params! {
+                ":event": event,
+                ":name": name,
+                ":mistake": mistake,
+                ":reporter": reporter,
+                ":context": context,
+            })?;
         ensure!(
             rows_changed == 1,
             format!("Failed to report mistake {mistake} of {name}")
         );
 
-        let mut select_stmt = self
-            .0
-            .prepare("SELECT * FROM Mistakes WHERE Name = :name AND Mistake = :mistake")?;
+        let params = named_params! {":event": event, ":name": name, ":mistake": mistake};
+        let mut select_stmt = self.0.prepare(
+            "SELECT COUNT(*) AS Count FROM MistakesLog
+             WHERE EventId = :event AND Name = :name AND Mistake = :mistake AND NOT Retracted",
+        )?;
         select_stmt
             .query_row(params, |row| {
                 Ok(PersonMistake {
@@ -443,21 +2131,327 @@ impl HebrewDb {
     }
 }
 
-fn unknown_word_err(word: &str) -> anyhow::Error {
-    anyhow!(format!("{} is an unknown word!", word))
+fn unknown_word_err(word: &str) -> DbError {
+    DbError::NotFound(format!("{} is an unknown word!", word))
+}
+
+fn already_suggested_err(suggestion: &SuggestedMistake) -> DbError {
+    DbError::Conflict(format!(
+        "{} was already suggested for {}",
+        suggestion.mistake.mistake, suggestion.mistake.name
+    ))
+}
+
+fn unknown_participant_err(name: &str) -> DbError {
+    DbError::NotFound(format!("{name} is an unknown participant!"))
+}
+
+fn unknown_event_err(id: i64) -> DbError {
+    DbError::NotFound(format!("There is no event with id {id}!"))
+}
+
+fn to_json(value: Option<impl Serialize>) -> Option<String> {
+    value.map(|value| json!(value).to_string())
+}
+
+/// Dates are compared as text, so they must be ISO `YYYY-MM-DD` to sort and filter correctly
+fn check_date_range(db: &Connection, start: Option<&str>, end: Option<&str>) -> Result<()> {
+    for date in [start, end].into_iter().flatten() {
+        let valid: bool = db.query_row("SELECT date(:date) IS :date", [date], |row| row.get(0))?;
+        ensure!(
+            valid,
+            DbError::Validation(format!("{date:?} is not a valid YYYY-MM-DD date"))
+        );
+    }
+    if let (Some(start), Some(end)) = (start, end) {
+        ensure!(
+            start <= end,
+            DbError::Validation(format!("{end} is before {start}"))
+        );
+    }
+    Ok(())
+}
+
+fn logged_mistake_from_row(row: &Row) -> rusqlite::Result<LoggedMistake> {
+    Ok(LoggedMistake {
+        id: row.get("ROWID")?,
+        event: row.get("EventId")?,
+        name: row.get("Name")?,
+        mistake: row.get("Mistake")?,
+        reporter: row.get("Reporter")?,
+        context: row.get("Context")?,
+        timestamp: row.get("Timestamp")?,
+        retracted: row.get("Retracted")?,
+    })
+}
+
+fn appeal_from_row(row: &Row) -> rusqlite::Result<Appeal> {
+    Ok(Appeal {
+        id: row.get("Id")?,
+        report: LoggedMistake {
+            id: row.get("ReportId")?,
+            event: row.get("EventId")?,
+            name: row.get("Name")?,
+            mistake: row.get("Mistake")?,
+            reporter: row.get("Reporter")?,
+            context: row.get("Context")?,
+            timestamp: row.get("Timestamp")?,
+            retracted: row.get("Retracted")?,
+        },
+        reason: row.get("Reason")?,
+        appellant: row.get("Appellant")?,
+        status: row.get("Status")?,
+        moderator: row.get("Moderator")?,
+        response: row.get("Response")?,
+        created_at: row.get("CreatedAt")?,
+        decided_at: row.get("DecidedAt")?,
+    })
+}
+
+fn mistake_correction_from_row(row: &Row) -> rusqlite::Result<MistakeCorrectionRecord> {
+    Ok(MistakeCorrectionRecord {
+        id: row.get("ROWID")?,
+        report_id: row.get("ReportId")?,
+        action: row.get("Action")?,
+        old_name: row.get("OldName")?,
+        old_mistake: row.get("OldMistake")?,
+        new_name: row.get("NewName")?,
+        new_mistake: row.get("NewMistake")?,
+        moderator: row.get("Moderator")?,
+        timestamp: row.get("Timestamp")?,
+    })
+}
+
+fn auto_approved_mistake_from_row(row: &Row) -> rusqlite::Result<AutoApprovedMistake> {
+    Ok(AutoApprovedMistake {
+        id: row.get("ROWID")?,
+        suggestion: SuggestedMistake {
+            mistake: MistakeSuggestion {
+                id: row.get("ROWID")?,
+                name: row.get("Name")?,
+                mistake: row.get("Mistake")?,
+                context: row.get("Context")?,
+            },
+            reporter: row.get("Reporter")?,
+            reporter_stats: None,
+            witnesses: None,
+        },
+        report_id: row.get("ReportId")?,
+        decision: row.get("Decision")?,
+        timestamp: row.get("Timestamp")?,
+    })
 }
 
-#[derive(Debug, Clone, Copy)]
-enum DbFieldType {
-    Int,
-    String,
+fn blocked_reporter_from_row(row: &Row) -> rusqlite::Result<BlockedReporter> {
+    Ok(BlockedReporter {
+        reporter: row.get("Reporter")?,
+        reason: row.get("Reason")?,
+        blocked_by: row.get("BlockedBy")?,
+        timestamp: row.get("Timestamp")?,
+    })
 }
 
-impl DbFieldType {
-    pub fn to_type_string(self) -> &'static str {
-        match self {
-            Self::Int => "int",
-            Self::String => "varchar(40)",
+fn event_from_row(row: &Row) -> rusqlite::Result<WeekEvent> {
+    Ok(WeekEvent {
+        id: row.get("ROWID")?,
+        name: row.get("Name")?,
+        start_date: row.get("StartDate")?,
+        end_date: row.get("EndDate")?,
+        status: row.get("Status")?,
+    })
+}
+
+/// Stores a unit-only enum as the text of its variant names
+macro_rules! sql_text_enum {
+    ($enum:ident { $($variant:ident),+ $(,)? }) => {
+        impl ToSql for $enum {
+            fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                let text = match self {
+                    $(Self::$variant => stringify!($variant),)+
+                };
+                Ok(text.into())
+            }
+        }
+
+        impl FromSql for $enum {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                match value.as_str()? {
+                    $(stringify!($variant) => Ok(Self::$variant),)+
+                    other => Err(FromSqlError::Other(
+                        anyhow!("Unknown {} {other}", stringify!($enum)).into(),
+                    )),
+                }
+            }
         }
+    };
+}
+
+sql_text_enum!(EventStatus {
+    Planned,
+    Active,
+    Finished
+});
+sql_text_enum!(Role {
+    Moderator,
+    DictionaryEditor,
+    SuperAdmin
+});
+sql_text_enum!(AuditAction {
+    AddEvent,
+    SetEventStatus,
+    AddParticipant,
+    UpdateParticipant,
+    RenameParticipant,
+    MergeParticipant,
+    RemoveParticipant,
+    ReportMistake,
+    RetractMistake,
+    CorrectMistake,
+    SuggestMistake,
+    AcceptMistakeSuggestion,
+    RejectMistakeSuggestion,
+    SuggestTranslation,
+    AddTranslation,
+    AcceptTranslationSuggestion,
+    RejectTranslationSuggestion,
+    AddCanonical,
+    AddAdminUser,
+    RemoveAdminUser,
+    ChangePassword,
+    Login,
+    Logout,
+    RevokeSessions,
+    EnrollTotp,
+    ConfirmTotp,
+    RemoveTotp,
+    BlockReporter,
+    UnblockReporter,
+    AutoApproveMistakeSuggestion,
+    RevertAutoApproval,
+    WitnessMistakeSuggestion,
+    AppealMistake,
+    UpholdAppeal,
+    OverturnAppeal
+});
+sql_text_enum!(AppealStatus {
+    Pending,
+    Upheld,
+    Overturned
+});
+sql_text_enum!(SuggestionDecision {
+    Accepted,
+    Rejected,
+    Auto,
+    Reverted
+});
+
+sql_text_enum!(CorrectionAction {
+    Retracted,
+    Corrected
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// As created before migrations existed
+    const BASELINE_SCHEMA: &str = "
+        CREATE TABLE Participants (Name varchar(40), CONSTRAINT u UNIQUE(Name));
+        CREATE TABLE Mistakes (Name varchar(40), Mistake varchar(40), Count int,
+            CONSTRAINT u UNIQUE(Name, Mistake));
+        CREATE TABLE MistakesSuggestions (Name varchar(40), Mistake varchar(40),
+            Context varchar(40), Reporter varchar(40));
+        CREATE TABLE MistakesSuggestionsArchive (Name varchar(40), Mistake varchar(40),
+            Context varchar(40), Reporter varchar(40), Accepted int);
+        CREATE TABLE Translations (English varchar(40), Hebrew varchar(40), Suggestor varchar(40),
+            CONSTRAINT u UNIQUE(English, Hebrew));
+        CREATE TABLE TranslationsSuggestions (English varchar(40), Hebrew varchar(40),
+            Suggestor varchar(40));
+        CREATE TABLE CanonicalWords (Word varchar(40), Canonical varchar(40),
+            CONSTRAINT u UNIQUE(Word));";
+
+    #[test]
+    fn fresh_databases_are_migrated_to_the_latest_version() {
+        let db = HebrewDb::in_memory().unwrap();
+        assert_eq!(db.schema_version().unwrap(), migrations::latest_version());
+        assert_eq!(db.active_event().unwrap().name, "Hebrew Week");
+    }
+
+    #[test]
+    fn baseline_databases_keep_their_mistakes() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(BASELINE_SCHEMA).unwrap();
+        connection
+            .execute_batch(
+                "INSERT INTO Participants VALUES('Yossi');
+                 INSERT INTO Mistakes VALUES('yossi', 'sorry', 2);
+                 INSERT INTO CanonicalWords VALUES('sorry', 'sorry');
+                 INSERT INTO MistakesSuggestions VALUES('YOSSI', 'okay', '', '192.0.2.1');",
+            )
+            .unwrap();
+        let db = HebrewDb::open(connection).unwrap();
+        assert_eq!(db.schema_version().unwrap(), migrations::latest_version());
+
+        let leaderboard = db
+            .leaderboard(LeaderboardQuery {
+                event: None,
+                from: None,
+                to: None,
+            })
+            .unwrap();
+        assert_eq!(leaderboard.len(), 1);
+        assert_eq!(leaderboard[0].name, "Yossi");
+        assert_eq!(leaderboard[0].total, 2);
+
+        let log = db.mistakes_log("Yossi", None, None).unwrap();
+        assert!(log
+            .iter()
+            .all(|report| report.name == "Yossi" && report.timestamp.is_none()));
+
+        let suggestions = db
+            .all_mistake_suggestions(None, &SuggestionFilter::default(), false)
+            .unwrap();
+        assert_eq!(suggestions[0].mistake.name, "Yossi");
+    }
+
+    #[test]
+    fn reverting_an_auto_approval_of_a_retracted_report() {
+        let db = HebrewDb::in_memory().unwrap();
+        let admin = Actor {
+            name: "admin".to_string(),
+            ip: None,
+        };
+        db.0.execute_batch("INSERT INTO CanonicalWords VALUES('sorry', 'sorry');")
+            .unwrap();
+        db.add_participant("Yossi", &admin).unwrap();
+        let auto_approval = AutoApproval {
+            reporters: vec!["trusted".to_string()],
+            min_acceptance_rate: None,
+            min_moderated: 0,
+            witness_quorum: None,
+        };
+        let suggestion = SuggestedMistake {
+            mistake: MistakeSuggestion {
+                id: 0,
+                name: "Yossi".to_string(),
+                mistake: "sorry".to_string(),
+                context: String::new(),
+            },
+            reporter: "trusted".to_string(),
+            reporter_stats: None,
+            witnesses: None,
+        };
+        let outcome = db
+            .suggest_mistake(suggestion, Duration::ZERO, &auto_approval, &admin)
+            .unwrap();
+        assert!(outcome.auto_approved);
+
+        let approved = &db.auto_approved_mistakes(None).unwrap()[0];
+        db.retract_mistake(approved.report_id, &admin).unwrap();
+        db.revert_auto_approval(outcome.id, &admin).unwrap();
+        assert_eq!(
+            db.auto_approved_mistakes(None).unwrap()[0].decision,
+            SuggestionDecision::Reverted
+        );
     }
 }
@@ -1,11 +1,19 @@
 mod api;
 mod auth;
+mod client_ip;
+mod config;
 mod error;
 mod hebrew_db;
+mod migrations;
+mod reporters;
+mod throttle;
+mod totp;
 mod types;
 
 use crate::api::routes;
 use crate::api::AppState;
+use crate::auth::{check_new_password, hash_password};
+use crate::hebrew_db::HebrewDb;
 use hyper::Method;
 use std::net::SocketAddr;
 use tower_http::cors::{Any, CorsLayer};
@@ -15,20 +23,61 @@ use tracing_subscriber::fmt::init;
 async fn main() {
     init();
 
+    match std::env::args().nth(1).as_deref() {
+        None => serve().await,
+        Some("migrate") => migrate(),
+        Some("hash-password") => print_password_hash(),
+        Some(command) => {
+            eprintln!(
+                "Unknown command {command}, expected no command, `migrate` or `hash-password`"
+            );
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Reads a password from stdin and prints its hash, e.g. `hebrew-week hash-password > p.ass`
+fn print_password_hash() {
+    eprintln!("Enter the new admin password:");
+    let mut password = String::new();
+    std::io::stdin()
+        .read_line(&mut password)
+        .expect("Failed reading password");
+    let password = password.trim_end_matches(['\r', '\n']);
+    if let Err(err) = check_new_password(password) {
+        eprintln!("{err}");
+        std::process::exit(2);
+    }
+    println!(
+        "{}",
+        hash_password(password).expect("Failed hashing password")
+    );
+}
+
+fn migrate() {
+    let db = HebrewDb::new().expect("Failed migrating db");
+    println!(
+        "hebrew.db is at schema version {}",
+        db.schema_version().expect("Failed reading schema version")
+    );
+}
+
+async fn serve() {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_headers(Any)
         .allow_methods([Method::GET, Method::POST, Method::DELETE]);
 
-    let app = routes()
-        .with_state(AppState::new().expect("Failed creating app state"))
-        .layer(cors);
+    let app = routes(AppState::new().expect("Failed creating app state")).layer(cors);
 
     // run it
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     println!("listening on {}", addr);
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -0,0 +1,581 @@
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use rusqlite::{named_params, Connection};
+
+use crate::types::EventStatus;
+
+/// A schema change, applied at most once per database.
+/// The schema version of a database is the number of migrations applied to it.
+struct Migration {
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// Append-only! Released migrations may already have been applied to deployed databases
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Initial schema",
+        apply: initial_schema,
+    },
+    Migration {
+        description: "Events, mistakes log, moderation archives and participant references",
+        apply: events_and_mistakes_log,
+    },
+    Migration {
+        description: "Admin users",
+        apply: admin_users,
+    },
+    Migration {
+        description: "Admin sessions",
+        apply: admin_sessions,
+    },
+    Migration {
+        description: "Admin TOTP enrollments and recovery codes",
+        apply: admin_totp,
+    },
+    Migration {
+        description: "Audit log",
+        apply: audit_log,
+    },
+    Migration {
+        description: "Suggestion timestamps and blocked reporters",
+        apply: blocked_reporters,
+    },
+    Migration {
+        description: "Raw reporter IPs alongside their display names",
+        apply: reporter_ips,
+    },
+    Migration {
+        description: "Suggestion decisions, for reverting auto-approvals",
+        apply: suggestion_decisions,
+    },
+    Migration {
+        description: "Witnesses of mistake suggestions",
+        apply: suggestion_witnesses,
+    },
+    Migration {
+        description: "Appeals of mistake reports",
+        apply: appeals,
+    },
+    Migration {
+        description: "Repeated appeals of a mistake report",
+        apply: repeated_appeals,
+    },
+];
+
+/// The schema version this binary expects
+pub(crate) fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub(crate) fn schema_version(db: &Connection) -> Result<u32> {
+    Ok(db.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Applies every pending migration in order, each in its own transaction
+pub(crate) fn migrate(db: &Connection) -> Result<()> {
+    let version = schema_version(db)?;
+    if version > latest_version() {
+        bail!(
+            "Database schema version {version} is newer than the latest version {} known to this binary",
+            latest_version()
+        );
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let version = index as u32 + 1;
+        tracing::info!(
+            "Migrating database to version {version}: {}",
+            migration.description
+        );
+        let transaction = db.unchecked_transaction()?;
+        (migration.apply)(&transaction)
+            .context(format!("Failed migrating database to version {version}"))?;
+        transaction.pragma_update(None, "user_version", version)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+fn initial_schema(db: &Connection) -> Result<()> {
+    create_table(db, "Participants", [("Name", DbFieldType::String)], [], [])?;
+    create_table(
+        db,
+        "Mistakes",
+        [
+            ("Name", DbFieldType::String),
+            ("Mistake", DbFieldType::String),
+        ],
+        [("Count", DbFieldType::Int)],
+        [],
+    )?;
+    create_table(
+        db,
+        "MistakesSuggestions",
+        [],
+        [
+            ("Name", DbFieldType::String),
+            ("Mistake", DbFieldType::String),
+            ("Context", DbFieldType::String),
+            ("Reporter", DbFieldType::String),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "MistakesSuggestionsArchive",
+        [],
+        [
+            ("Name", DbFieldType::String),
+            ("Mistake", DbFieldType::String),
+            ("Context", DbFieldType::String),
+            ("Reporter", DbFieldType::String),
+            ("Accepted", DbFieldType::Int),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "Translations",
+        [
+            ("English", DbFieldType::String),
+            ("Hebrew", DbFieldType::String),
+        ],
+        [("Suggestor", DbFieldType::String)],
+        [],
+    )?;
+    create_table(
+        db,
+        "TranslationsSuggestions",
+        [
+            ("English", DbFieldType::String),
+            ("Hebrew", DbFieldType::String),
+        ],
+        [("Suggestor", DbFieldType::String)],
+        [],
+    )?;
+    create_table(
+        db,
+        "CanonicalWords",
+        [("Word", DbFieldType::String)],
+        [("Canonical", DbFieldType::String)],
+        [],
+    )?;
+    Ok(())
+}
+
+/// Moves everything recorded so far into a first event, and expands the mistake counters into
+/// individual reports, left undated so date ranges don't count them
+fn events_and_mistakes_log(db: &Connection) -> Result<()> {
+    for table in [
+        "Participants",
+        "MistakesSuggestions",
+        "MistakesSuggestionsArchive",
+    ] {
+        db.execute(&format!("ALTER TABLE {table} RENAME TO {table}Old"), ())?;
+    }
+
+    create_table(
+        db,
+        "Events",
+        [("Name", DbFieldType::String)],
+        [
+            ("StartDate", DbFieldType::String),
+            ("EndDate", DbFieldType::String),
+            ("Status", DbFieldType::String),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "Participants",
+        [
+            ("EventId", DbFieldType::Int),
+            ("Name", DbFieldType::NoCaseString),
+        ],
+        [
+            ("DisplayName", DbFieldType::String),
+            ("Team", DbFieldType::String),
+            ("AvatarUrl", DbFieldType::String),
+            ("Active", DbFieldType::Int),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "MistakesLog",
+        [],
+        [
+            ("EventId", DbFieldType::Int),
+            ("Name", DbFieldType::NoCaseString),
+            ("Mistake", DbFieldType::String),
+            ("Reporter", DbFieldType::String),
+            ("Context", DbFieldType::String),
+            ("Timestamp", DbFieldType::String),
+            ("Retracted", DbFieldType::Int),
+        ],
+        [PARTICIPANT_KEY],
+    )?;
+    create_table(
+        db,
+        "MistakesCorrections",
+        [],
+        [
+            ("ReportId", DbFieldType::Int),
+            ("Action", DbFieldType::String),
+            ("OldName", DbFieldType::String),
+            ("OldMistake", DbFieldType::String),
+            ("NewName", DbFieldType::String),
+            ("NewMistake", DbFieldType::String),
+            ("Moderator", DbFieldType::String),
+            ("Timestamp", DbFieldType::String),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "MistakesSuggestions",
+        [],
+        [
+            ("EventId", DbFieldType::Int),
+            ("Name", DbFieldType::NoCaseString),
+            ("Mistake", DbFieldType::String),
+            ("Context", DbFieldType::String),
+            ("Reporter", DbFieldType::String),
+        ],
+        [PARTICIPANT_KEY],
+    )?;
+    create_table(
+        db,
+        "MistakesSuggestionsArchive",
+        [],
+        [
+            ("EventId", DbFieldType::Int),
+            ("Name", DbFieldType::NoCaseString),
+            ("Mistake", DbFieldType::String),
+            ("Context", DbFieldType::String),
+            ("Reporter", DbFieldType::String),
+            ("Accepted", DbFieldType::Int),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "TranslationsSuggestionsArchive",
+        [],
+        [
+            ("English", DbFieldType::String),
+            ("Hebrew", DbFieldType::String),
+            ("Suggestor", DbFieldType::String),
+            ("Accepted", DbFieldType::Int),
+            ("Moderator", DbFieldType::String),
+            ("Timestamp", DbFieldType::String),
+        ],
+        [],
+    )?;
+
+    db.execute(
+        "INSERT INTO Events VALUES('Hebrew Week', date('now'), date('now', '+7 days'), :status)",
+        [EventStatus::Active],
+    )?;
+    let event = db.last_insert_rowid();
+    // Mistakes used to be reported for unlisted names, and suggestions still reference them
+    for statement in [
+        "INSERT OR IGNORE INTO Participants (EventId, Name, Active)
+         SELECT :event, Name, TRUE FROM ParticipantsOld",
+        "INSERT OR IGNORE INTO Participants (EventId, Name, Active)
+         SELECT :event, Name, TRUE FROM Mistakes",
+        "INSERT OR IGNORE INTO Participants (EventId, Name, Active)
+         SELECT :event, Name, FALSE FROM MistakesSuggestionsOld",
+        "WITH RECURSIVE Reports(Name, Mistake, Remaining) AS (
+             SELECT Name, Mistake, Count FROM Mistakes WHERE Count > 0
+             UNION ALL
+             SELECT Name, Mistake, Remaining - 1 FROM Reports WHERE Remaining > 1
+         )
+         INSERT INTO MistakesLog
+         SELECT :event, Name, Mistake, 'unknown', NULL, NULL, FALSE FROM Reports",
+        "INSERT INTO MistakesSuggestions (ROWID, EventId, Name, Mistake, Context, Reporter)
+         SELECT ROWID, :event, Name, Mistake, Context, Reporter FROM MistakesSuggestionsOld",
+        "INSERT INTO MistakesSuggestionsArchive
+         SELECT :event, Name, Mistake, Context, Reporter, Accepted FROM MistakesSuggestionsArchiveOld",
+        // Names only used to match exactly, so they're spelled like the participant they now refer to
+        "UPDATE MistakesLog SET Name = (SELECT Participants.Name FROM Participants
+         WHERE Participants.EventId = :event AND Participants.Name = MistakesLog.Name)",
+        "UPDATE MistakesSuggestions SET Name = (SELECT Participants.Name FROM Participants
+         WHERE Participants.EventId = :event AND Participants.Name = MistakesSuggestions.Name)",
+    ] {
+        db.execute(statement, named_params! {":event": event})?;
+    }
+
+    for table in [
+        "Mistakes",
+        "ParticipantsOld",
+        "MistakesSuggestionsOld",
+        "MistakesSuggestionsArchiveOld",
+    ] {
+        db.execute(&format!("DROP TABLE {table}"), ())?;
+    }
+    Ok(())
+}
+
+fn admin_users(db: &Connection) -> Result<()> {
+    create_table(
+        db,
+        "AdminUsers",
+        [("Username", DbFieldType::NoCaseString)],
+        [("Hash", DbFieldType::Text), ("Role", DbFieldType::String)],
+        [],
+    )
+}
+
+/// Only token hashes are stored, so a leaked database can't be used to log in
+fn admin_sessions(db: &Connection) -> Result<()> {
+    create_table(
+        db,
+        "AdminSessions",
+        [("TokenHash", DbFieldType::Text)],
+        [
+            ("Username", DbFieldType::NoCaseString),
+            ("Role", DbFieldType::String),
+            ("CreatedAt", DbFieldType::String),
+            ("ExpiresAt", DbFieldType::String),
+        ],
+        [],
+    )
+}
+
+fn admin_totp(db: &Connection) -> Result<()> {
+    create_table(
+        db,
+        "AdminTotp",
+        [("Username", DbFieldType::NoCaseString)],
+        [
+            ("Secret", DbFieldType::Text),
+            ("Confirmed", DbFieldType::Int),
+            ("LastStep", DbFieldType::Int),
+        ],
+        [],
+    )?;
+    create_table(
+        db,
+        "AdminRecoveryCodes",
+        [
+            ("Username", DbFieldType::NoCaseString),
+            ("CodeHash", DbFieldType::Text),
+        ],
+        [],
+        [],
+    )
+}
+
+fn audit_log(db: &Connection) -> Result<()> {
+    create_table(
+        db,
+        "AuditLog",
+        [],
+        [
+            ("Actor", DbFieldType::NoCaseString),
+            ("Ip", DbFieldType::String),
+            ("Action", DbFieldType::String),
+            ("Before", DbFieldType::Text),
+            ("After", DbFieldType::Text),
+            ("Timestamp", DbFieldType::String),
+        ],
+        [],
+    )?;
+    db.execute_batch(
+        "CREATE TRIGGER AuditLogNoUpdate BEFORE UPDATE ON AuditLog
+         BEGIN SELECT RAISE(ABORT, 'The audit log is append-only'); END;
+         CREATE TRIGGER AuditLogNoDelete BEFORE DELETE ON AuditLog
+         BEGIN SELECT RAISE(ABORT, 'The audit log is append-only'); END;",
+    )?;
+    Ok(())
+}
+
+fn blocked_reporters(db: &Connection) -> Result<()> {
+    db.execute(
+        "ALTER TABLE MistakesSuggestions ADD COLUMN Timestamp varchar(40)",
+        (),
+    )?;
+    create_table(
+        db,
+        "BlockedReporters",
+        [("Reporter", DbFieldType::NoCaseString)],
+        [
+            ("Reason", DbFieldType::Text),
+            ("BlockedBy", DbFieldType::NoCaseString),
+            ("Timestamp", DbFieldType::String),
+        ],
+        [],
+    )
+}
+
+fn reporter_ips(db: &Connection) -> Result<()> {
+    db.execute_batch(
+        "ALTER TABLE MistakesSuggestions ADD COLUMN ReporterIp varchar(40);
+         ALTER TABLE MistakesSuggestionsArchive ADD COLUMN ReporterIp varchar(40);
+         ALTER TABLE TranslationsSuggestions ADD COLUMN SuggestorIp varchar(40);
+         ALTER TABLE TranslationsSuggestionsArchive ADD COLUMN SuggestorIp varchar(40);",
+    )?;
+    Ok(())
+}
+
+fn suggestion_decisions(db: &Connection) -> Result<()> {
+    db.execute_batch(
+        "ALTER TABLE MistakesSuggestionsArchive ADD COLUMN Decision varchar(40);
+         ALTER TABLE MistakesSuggestionsArchive ADD COLUMN ReportId int;
+         ALTER TABLE MistakesSuggestionsArchive ADD COLUMN Timestamp varchar(40);
+         UPDATE MistakesSuggestionsArchive
+         SET Decision = CASE WHEN Accepted THEN 'Accepted' ELSE 'Rejected' END;",
+    )?;
+    Ok(())
+}
+
+fn suggestion_witnesses(db: &Connection) -> Result<()> {
+    create_table(
+        db,
+        "MistakesSuggestionsWitnesses",
+        [
+            ("SuggestionId", DbFieldType::Int),
+            ("Reporter", DbFieldType::NoCaseString),
+        ],
+        [
+            ("ReporterIp", DbFieldType::String),
+            ("Timestamp", DbFieldType::String),
+        ],
+        [],
+    )?;
+    db.execute(
+        "INSERT INTO MistakesSuggestionsWitnesses
+         SELECT ROWID, Reporter, ReporterIp, Timestamp FROM MistakesSuggestions",
+        (),
+    )?;
+    Ok(())
+}
+
+fn appeals(db: &Connection) -> Result<()> {
+    create_table(
+        db,
+        "Appeals",
+        [("ReportId", DbFieldType::Int)],
+        [
+            ("Reason", DbFieldType::Text),
+            ("Appellant", DbFieldType::NoCaseString),
+            ("AppellantIp", DbFieldType::String),
+            ("Status", DbFieldType::String),
+            ("Moderator", DbFieldType::NoCaseString),
+            ("Response", DbFieldType::Text),
+            ("CreatedAt", DbFieldType::String),
+            ("DecidedAt", DbFieldType::String),
+        ],
+        [],
+    )
+}
+
+/// A report may be appealed again once its previous appeal was upheld,
+/// so a throwaway appeal can't block the participant's own
+fn repeated_appeals(db: &Connection) -> Result<()> {
+    db.execute("ALTER TABLE Appeals RENAME TO AppealsOld", ())?;
+    create_table(
+        db,
+        "Appeals",
+        [],
+        [
+            ("ReportId", DbFieldType::Int),
+            ("Reason", DbFieldType::Text),
+            ("Appellant", DbFieldType::NoCaseString),
+            ("AppellantIp", DbFieldType::String),
+            ("Status", DbFieldType::String),
+            ("Moderator", DbFieldType::NoCaseString),
+            ("Response", DbFieldType::Text),
+            ("CreatedAt", DbFieldType::String),
+            ("DecidedAt", DbFieldType::String),
+        ],
+        [],
+    )?;
+    db.execute(
+        "INSERT INTO Appeals (ROWID, ReportId, Reason, Appellant, AppellantIp, Status, Moderator,
+         Response, CreatedAt, DecidedAt)
+         SELECT ROWID, * FROM AppealsOld",
+        (),
+    )?;
+    db.execute("DROP TABLE AppealsOld", ())?;
+    Ok(())
+}
+
+fn create_table<
+    IUnique: IntoIterator<Item = (&'static str, DbFieldType)> + Copy,
+    IOther: IntoIterator<Item = (&'static str, DbFieldType)>,
+    IForeign: IntoIterator<Item = ForeignKey>,
+>(
+    db: &Connection,
+    table_name: &str,
+    unique_fields: IUnique,
+    other_fields: IOther,
+    foreign_keys: IForeign,
+) -> Result<()> {
+    let fields = unique_fields
+        .into_iter()
+        .chain(other_fields)
+        .map(|(name, field_type)| format!("{} {}", name, field_type.to_type_string()))
+        .collect::<Vec<String>>()
+        .join(",\n");
+    let constraint = if unique_fields.into_iter().peekable().peek().is_some() {
+        format!(
+            ", CONSTRAINT u UNIQUE({})",
+            unique_fields
+                .into_iter()
+                .map(|(name, _)| name.to_owned())
+                .join(", ")
+        )
+    } else {
+        String::from("")
+    };
+    let references: String = foreign_keys
+        .into_iter()
+        .map(|key| {
+            let fields = key.fields.join(", ");
+            format!(
+                ", FOREIGN KEY({fields}) REFERENCES {}({fields}) ON UPDATE CASCADE",
+                key.table
+            )
+        })
+        .collect();
+    let table_query =
+        format!("CREATE TABLE IF NOT EXISTS {table_name} ({fields}{constraint}{references});");
+    db.execute(&table_query, ())
+        .context(format!("Failed creating table {table_name}"))?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DbFieldType {
+    Int,
+    String,
+    /// A string of unbounded length
+    Text,
+    /// A string compared case-insensitively, so "Yossi" and "yossi" are the same
+    NoCaseString,
+}
+
+impl DbFieldType {
+    pub fn to_type_string(self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::String => "varchar(40)",
+            Self::Text => "text",
+            Self::NoCaseString => "varchar(40) COLLATE NOCASE",
+        }
+    }
+}
+
+/// References the same-named (unique) fields of another table
+#[derive(Debug, Clone, Copy)]
+struct ForeignKey {
+    fields: &'static [&'static str],
+    table: &'static str,
+}
+
+const PARTICIPANT_KEY: ForeignKey = ForeignKey {
+    fields: &["EventId", "Name"],
+    table: "Participants",
+};
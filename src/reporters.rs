@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::config::{Config, ReporterNames};
+
+#[derive(Debug)]
+struct CachedHostname {
+    resolved_at: Instant,
+    hostname: Option<String>,
+}
+
+/// Names anonymous reporters by their IP, as configured
+#[derive(Debug)]
+pub struct ReporterResolver {
+    names: ReporterNames,
+    salt: String,
+    timeout: Duration,
+    ttl: Duration,
+    cache: Mutex<HashMap<IpAddr, CachedHostname>>,
+}
+
+impl ReporterResolver {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            names: config.reporter_names,
+            salt: config.reporter_salt.clone(),
+            timeout: config.dns_timeout,
+            ttl: config.dns_cache_ttl,
+            cache: Mutex::default(),
+        }
+    }
+
+    pub async fn name(&self, ip: IpAddr) -> String {
+        match self.names {
+            ReporterNames::Off => ip.to_string(),
+            ReporterNames::Hostname => self.hostname(ip).await.unwrap_or_else(|| ip.to_string()),
+            ReporterNames::Hashed => {
+                let hash = hex::encode(Sha256::digest(format!("{}{ip}", self.salt)));
+                format!("anon-{}", &hash[..10])
+            }
+        }
+    }
+
+    /// Reverse resolves on a blocking thread, giving up after the timeout.
+    /// Whoever controls the reverse zone of an IP can claim any hostname for it, so names are
+    /// used as identities (for auto-approval and reporter stats) only if they resolve back to it.
+    async fn hostname(&self, ip: IpAddr) -> Option<String> {
+        if let Some(cached) = self.cache.lock().unwrap().get(&ip) {
+            if cached.resolved_at.elapsed() < self.ttl {
+                return cached.hostname.clone();
+            }
+        }
+        let lookup = tokio::task::spawn_blocking(move || {
+            let hostname = dns_lookup::lookup_addr(&ip).ok()?;
+            let confirmed =
+                dns_lookup::lookup_host(&hostname).is_ok_and(|addresses| addresses.contains(&ip));
+            if !confirmed {
+                tracing::warn!(
+                    "{ip} reverse resolves to {hostname}, which doesn't resolve back to it"
+                );
+            }
+            confirmed.then_some(hostname)
+        });
+        let hostname = match tokio::time::timeout(self.timeout, lookup).await {
+            Ok(Ok(hostname)) => hostname,
+            Ok(Err(err)) => {
+                tracing::warn!("Reverse lookup of {ip} failed: {err}");
+                None
+            }
+            Err(_) => {
+                tracing::debug!("Reverse lookup of {ip} timed out");
+                None
+            }
+        };
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, cached| cached.resolved_at.elapsed() < self.ttl);
+        cache.insert(
+            ip,
+            CachedHostname {
+                resolved_at: Instant::now(),
+                hostname: hostname.clone(),
+            },
+        );
+        hostname
+    }
+}
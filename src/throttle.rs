@@ -0,0 +1,313 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Failed logins allowed before backing off
+const FREE_ATTEMPTS: u32 = 3;
+/// The first lockout, doubled by each further failure
+const BASE_LOCKOUT: Duration = Duration::from_secs(2);
+const MAX_LOCKOUT: Duration = Duration::from_secs(15 * 60);
+/// Failures are forgotten after this long without another one
+const FORGET_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// What failed logins and suggestions are counted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ThrottleKey {
+    Ip(IpAddr),
+    /// Usernames are case-insensitive, so keys are lowercased
+    Account(String),
+    /// The (resolved) name suggestions are recorded under
+    Reporter(String),
+}
+
+impl ThrottleKey {
+    pub fn account(username: &str) -> Self {
+        Self::Account(username.to_lowercase())
+    }
+}
+
+#[derive(Debug)]
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+    /// Attempts still being verified, which may all turn out to be failures
+    in_flight: u32,
+}
+
+impl Failures {
+    fn new(now: Instant) -> Self {
+        Self {
+            count: 0,
+            last: now,
+            locked_until: None,
+            in_flight: 0,
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.count == 0 && self.in_flight == 0
+    }
+}
+
+/// Tracks failed logins per IP and per account, locking them out with exponential backoff
+#[derive(Debug, Default)]
+pub struct LoginThrottle {
+    failures: HashMap<ThrottleKey, Failures>,
+}
+
+impl LoginThrottle {
+    /// How long until any of `keys` may try logging in again, if it is locked out
+    fn retry_after(&self, keys: &[ThrottleKey]) -> Option<Duration> {
+        let now = Instant::now();
+        keys.iter()
+            .filter_map(|key| self.failures.get(key)?.locked_until)
+            .filter(|until| *until > now)
+            .max()
+            .map(|until| until - now)
+    }
+
+    /// Counts an attempt as in flight unless any of `keys` is locked out, or has as many
+    /// attempts in flight as could lock it out, so concurrent guesses can't outrun the backoff
+    fn begin_attempt(&mut self, keys: &[ThrottleKey]) -> Result<(), Duration> {
+        if let Some(retry_after) = self.retry_after(keys) {
+            return Err(retry_after);
+        }
+        let saturated = keys.iter().any(|key| {
+            self.failures.get(key).is_some_and(|failures| {
+                failures.in_flight > 0 && failures.count + failures.in_flight > FREE_ATTEMPTS
+            })
+        });
+        if saturated {
+            tracing::warn!("Refused a concurrent login attempt for {keys:?}");
+            return Err(BASE_LOCKOUT);
+        }
+        let now = Instant::now();
+        for key in keys {
+            self.failures
+                .entry(key.clone())
+                .or_insert_with(|| Failures::new(now))
+                .in_flight += 1;
+        }
+        Ok(())
+    }
+
+    fn end_attempt(&mut self, keys: &[ThrottleKey]) {
+        for key in keys {
+            if let Some(failures) = self.failures.get_mut(key) {
+                failures.in_flight = failures.in_flight.saturating_sub(1);
+                if failures.is_idle() {
+                    self.failures.remove(key);
+                }
+            }
+        }
+    }
+
+    fn record_failure(&mut self, keys: &[ThrottleKey]) {
+        let now = Instant::now();
+        self.failures
+            .retain(|_, failures| failures.in_flight > 0 || now - failures.last < FORGET_AFTER);
+        for key in keys {
+            let failures = self
+                .failures
+                .entry(key.clone())
+                .or_insert_with(|| Failures::new(now));
+            failures.count += 1;
+            failures.last = now;
+            if failures.count > FREE_ATTEMPTS {
+                let lockout = BASE_LOCKOUT
+                    .saturating_mul(2u32.saturating_pow(failures.count - FREE_ATTEMPTS - 1))
+                    .min(MAX_LOCKOUT);
+                failures.locked_until = Some(now + lockout);
+                tracing::warn!(
+                    "Locked out {key:?} for {}s after {} failed logins",
+                    lockout.as_secs(),
+                    failures.count
+                );
+            }
+        }
+    }
+
+    fn record_success(&mut self, keys: &[ThrottleKey]) {
+        for key in keys {
+            if let Some(failures) = self.failures.get_mut(key) {
+                failures.count = 0;
+                failures.locked_until = None;
+                if failures.is_idle() {
+                    self.failures.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// A login attempt counted as in flight by a `LoginThrottle` until dropped
+#[derive(Debug)]
+pub struct LoginAttempt {
+    throttle: Arc<Mutex<LoginThrottle>>,
+    keys: Vec<ThrottleKey>,
+}
+
+impl LoginAttempt {
+    /// Fails with how long to wait if the attempt may not be made now
+    pub fn begin(
+        throttle: &Arc<Mutex<LoginThrottle>>,
+        keys: &[ThrottleKey],
+    ) -> Result<Self, Duration> {
+        throttle.lock().unwrap().begin_attempt(keys)?;
+        Ok(Self {
+            throttle: throttle.clone(),
+            keys: keys.to_vec(),
+        })
+    }
+
+    pub fn failed(self) {
+        self.throttle.lock().unwrap().record_failure(&self.keys);
+    }
+
+    /// Forgets the failures of `keys`, typically only the account's, since others may share the IP
+    pub fn succeeded(self, keys: &[ThrottleKey]) {
+        self.throttle.lock().unwrap().record_success(keys);
+    }
+}
+
+impl Drop for LoginAttempt {
+    fn drop(&mut self) {
+        self.throttle.lock().unwrap().end_attempt(&self.keys);
+    }
+}
+
+/// Allows a limited number of suggestions per key within a sliding window
+#[derive(Debug, Default)]
+pub struct SuggestionLimiter {
+    hits: HashMap<ThrottleKey, VecDeque<Instant>>,
+}
+
+impl SuggestionLimiter {
+    /// Counts a suggestion against every key, unless one of them reached the limit,
+    /// in which case returns how long until it may suggest again
+    pub fn try_hit(
+        &mut self,
+        keys: &[ThrottleKey],
+        limit: u32,
+        window: Duration,
+    ) -> Result<(), Duration> {
+        let now = Instant::now();
+        self.hits.retain(|_, hits| {
+            while hits.front().is_some_and(|hit| now - *hit >= window) {
+                hits.pop_front();
+            }
+            !hits.is_empty()
+        });
+        let retry_after = keys
+            .iter()
+            .filter_map(|key| self.hits.get(key))
+            .filter(|hits| hits.len() >= limit as usize)
+            .filter_map(|hits| hits.front())
+            .map(|oldest| window - (now - *oldest))
+            .max();
+        if let Some(retry_after) = retry_after {
+            tracing::warn!("Rate limited suggestions from {keys:?}");
+            return Err(retry_after);
+        }
+        for key in keys {
+            self.hits.entry(key.clone()).or_default().push_back(now);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> [ThrottleKey; 2] {
+        [
+            ThrottleKey::Ip(IpAddr::from([192, 0, 2, 1])),
+            ThrottleKey::account("Admin"),
+        ]
+    }
+
+    #[test]
+    fn lockout_doubles_after_free_attempts() {
+        let mut throttle = LoginThrottle::default();
+        for _ in 0..FREE_ATTEMPTS {
+            throttle.record_failure(&keys());
+        }
+        assert_eq!(throttle.retry_after(&keys()), None);
+
+        for doublings in 0..4 {
+            throttle.record_failure(&keys());
+            let expected = BASE_LOCKOUT * 2u32.pow(doublings);
+            let lockout = throttle.retry_after(&keys()).unwrap();
+            assert!(lockout <= expected && lockout > expected - Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        let mut throttle = LoginThrottle::default();
+        for _ in 0..64 {
+            throttle.record_failure(&keys());
+        }
+        assert!(throttle.retry_after(&keys()).unwrap() <= MAX_LOCKOUT);
+    }
+
+    #[test]
+    fn success_forgets_only_the_given_keys() {
+        let mut throttle = LoginThrottle::default();
+        let [ip, account] = keys();
+        for _ in 0..=FREE_ATTEMPTS {
+            throttle.record_failure(&keys());
+        }
+        throttle.record_success(std::slice::from_ref(&account));
+        assert_eq!(throttle.retry_after(&[account]), None);
+        assert!(throttle.retry_after(&[ip]).is_some());
+    }
+
+    #[test]
+    fn concurrent_attempts_cant_outrun_the_backoff() {
+        let throttle = Arc::new(Mutex::new(LoginThrottle::default()));
+        let attempts: Vec<LoginAttempt> = (0..=FREE_ATTEMPTS)
+            .map(|_| LoginAttempt::begin(&throttle, &keys()).unwrap())
+            .collect();
+        assert!(LoginAttempt::begin(&throttle, &keys()).is_err());
+
+        attempts.into_iter().for_each(LoginAttempt::failed);
+        assert!(throttle.lock().unwrap().retry_after(&keys()).is_some());
+    }
+
+    #[test]
+    fn finished_attempts_stop_counting_as_in_flight() {
+        let throttle = Arc::new(Mutex::new(LoginThrottle::default()));
+        for _ in 0..10 {
+            let attempt = LoginAttempt::begin(&throttle, &keys()).unwrap();
+            attempt.succeeded(&keys());
+        }
+        assert!(throttle.lock().unwrap().failures.is_empty());
+    }
+
+    #[test]
+    fn suggestions_are_limited_within_the_window() {
+        let mut limiter = SuggestionLimiter::default();
+        let window = Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(limiter.try_hit(&keys(), 3, window), Ok(()));
+        }
+        let retry_after = limiter.try_hit(&keys(), 3, window).unwrap_err();
+        assert!(retry_after <= window);
+        // Refused suggestions aren't counted
+        assert_eq!(limiter.hits[&keys()[0]].len(), 3);
+    }
+
+    #[test]
+    fn suggestions_expire_after_the_window() {
+        let mut limiter = SuggestionLimiter::default();
+        let window = Duration::from_millis(20);
+        assert_eq!(limiter.try_hit(&keys(), 1, window), Ok(()));
+        assert!(limiter.try_hit(&keys(), 1, window).is_err());
+        std::thread::sleep(window);
+        assert_eq!(limiter.try_hit(&keys(), 1, window), Ok(()));
+    }
+}
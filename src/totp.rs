@@ -0,0 +1,174 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha1::Sha1;
+
+const ISSUER: &str = "Hebrew Week";
+const STEP_SECONDS: u64 = 30;
+const DIGITS: u32 = 6;
+/// Steps before and after the current one still accepted, for clock drift
+const ALLOWED_DRIFT: u64 = 1;
+const RECOVERY_CODES: usize = 10;
+
+/// An admin's enrollment, as stored in the database
+#[derive(Debug)]
+pub struct StoredTotp {
+    /// Base32 encoded
+    pub secret: String,
+    /// Whether the admin proved their app generates codes, which makes them required on login
+    pub confirmed: bool,
+    /// The last step a code was accepted for, so a code can't be used twice
+    pub last_step: Option<u64>,
+}
+
+/// A new base32 encoded secret
+pub fn new_secret() -> String {
+    let mut secret = [0u8; 20];
+    OsRng.fill_bytes(&mut secret);
+    BASE32_NOPAD.encode(&secret)
+}
+
+/// The URI authenticator apps enroll from, usually shown as a QR code
+pub fn otpauth_uri(username: &str, secret: &str) -> String {
+    let issuer = percent_encode(ISSUER);
+    format!(
+        "otpauth://totp/{issuer}:{}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}",
+        percent_encode(username)
+    )
+}
+
+/// Single-use codes for logging in without the authenticator app
+pub fn new_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let mut code = [0u8; 5];
+            OsRng.fill_bytes(&mut code);
+            let code = hex::encode(code);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// The step `code` is valid for, if it is valid now and newer than `last_step`
+pub fn verify(secret: &str, code: &str, last_step: Option<u64>) -> Result<Option<u64>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() / STEP_SECONDS;
+    verify_at(secret, code, last_step, now)
+}
+
+fn verify_at(secret: &str, code: &str, last_step: Option<u64>, now: u64) -> Result<Option<u64>> {
+    let secret = BASE32_NOPAD
+        .decode(secret.as_bytes())
+        .context("Invalid TOTP secret")?;
+    let code = code.trim();
+    Ok((now.saturating_sub(ALLOWED_DRIFT)..=now + ALLOWED_DRIFT)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| format!("{:0width$}", hotp(&secret, *step), width = DIGITS as usize) == code))
+}
+
+/// RFC 4226 HMAC-based one-time password
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let truncated = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    truncated % 10u32.pow(DIGITS)
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RFC 4226 and RFC 6238 (SHA-1) test secret
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    fn code(step: u64) -> String {
+        format!("{:06}", hotp(SECRET, step))
+    }
+
+    #[test]
+    fn hotp_matches_rfc_4226() {
+        let expected = [
+            755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489,
+        ];
+        for (counter, expected) in expected.into_iter().enumerate() {
+            assert_eq!(hotp(SECRET, counter as u64), expected);
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc_6238() {
+        // The RFC's 8 digit codes, truncated to our 6
+        let expected = [
+            (59, 287082),
+            (1111111109, 81804),
+            (1111111111, 50471),
+            (1234567890, 5924),
+            (2000000000, 279037),
+            (20000000000, 353130),
+        ];
+        for (time, expected) in expected {
+            assert_eq!(hotp(SECRET, time / STEP_SECONDS), expected);
+        }
+    }
+
+    #[test]
+    fn verify_allows_drift_of_one_step() {
+        let secret = BASE32_NOPAD.encode(SECRET);
+        let now = 1000;
+        for step in now - ALLOWED_DRIFT..=now + ALLOWED_DRIFT {
+            assert_eq!(
+                verify_at(&secret, &code(step), None, now).unwrap(),
+                Some(step)
+            );
+        }
+        for step in [now - ALLOWED_DRIFT - 1, now + ALLOWED_DRIFT + 1] {
+            assert_eq!(verify_at(&secret, &code(step), None, now).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn verify_refuses_reused_steps() {
+        let secret = BASE32_NOPAD.encode(SECRET);
+        let now = 1000;
+        assert_eq!(
+            verify_at(&secret, &code(now), Some(now), now).unwrap(),
+            None
+        );
+        assert_eq!(
+            verify_at(&secret, &code(now - 1), Some(now - 1), now).unwrap(),
+            None
+        );
+        assert_eq!(
+            verify_at(&secret, &code(now + 1), Some(now), now).unwrap(),
+            Some(now + 1)
+        );
+    }
+
+    #[test]
+    fn verify_ignores_surrounding_whitespace() {
+        let secret = BASE32_NOPAD.encode(SECRET);
+        let code = format!(" {}\n", code(1000));
+        assert_eq!(verify_at(&secret, &code, None, 1000).unwrap(), Some(1000));
+        assert!(verify_at("not base32!", "123456", None, 1000).is_err());
+    }
+}
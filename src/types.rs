@@ -2,12 +2,46 @@ use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use tsync::tsync;
 
+use crate::error::ErrorBody;
+
 // TODO serialize?
 #[tsync]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MistakeReport {
     pub name: String,
     pub mistake: String,
+    pub context: Option<String>,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct Participant {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub team: Option<String>,
+    pub avatar_url: Option<String>,
+    pub active: bool,
+}
+
+#[tsync]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParticipantMetadata {
+    pub display_name: Option<String>,
+    pub team: Option<String>,
+    pub avatar_url: Option<String>,
+    pub active: bool,
+}
+
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct ParticipantRename {
+    pub name: String,
+}
+
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct ParticipantMerge {
+    pub into: String,
 }
 
 #[tsync]
@@ -24,6 +58,62 @@ pub struct MistakeSuggestion {
 pub struct SuggestedMistake {
     pub mistake: MistakeSuggestion,
     pub reporter: String,
+    /// Only filled in when listing pending suggestions to moderators
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reporter_stats: Option<ReporterStats>,
+    /// Every reporter who suggested it, the original reporter first.
+    /// Only filled in when listing pending suggestions to moderators.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witnesses: Option<Vec<String>>,
+}
+
+/// How a reporter's mistake suggestions were moderated, across every event
+#[tsync]
+#[derive(Debug, Clone, Serialize)]
+pub struct ReporterStats {
+    pub reporter: String,
+    pub submitted: u32,
+    pub accepted: u32,
+    pub rejected: u32,
+    pub pending: u32,
+    /// Accepted by an auto-approval rule, which doesn't count towards the acceptance rate
+    pub auto_approved: u32,
+    /// Of the suggestions moderated by an admin so far, none if there aren't any
+    pub acceptance_rate: Option<f64>,
+}
+
+/// How an archived suggestion was decided on
+#[tsync]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SuggestionDecision {
+    Accepted,
+    Rejected,
+    /// Accepted by an auto-approval rule
+    Auto,
+    /// Auto-approved, then reverted by an admin
+    Reverted,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct SuggestionOutcome {
+    /// The pending suggestion, or if it was auto-approved, the archived one
+    pub id: i64,
+    pub auto_approved: bool,
+    /// Reporters who suggested it so far, when identical suggestions were merged
+    pub witnesses: u32,
+}
+
+/// A suggestion accepted by an auto-approval rule, which an admin may revert
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct AutoApprovedMistake {
+    pub id: i64,
+    pub suggestion: SuggestedMistake,
+    /// The mistake report it was recorded as
+    pub report_id: i64,
+    pub decision: SuggestionDecision,
+    pub timestamp: String,
 }
 
 #[tsync]
@@ -47,6 +137,99 @@ pub struct PersonMistake {
     pub counted_mistake: CountedMistake,
 }
 
+/// A single report of a mistake, as kept in the mistakes log
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct LoggedMistake {
+    pub id: i64,
+    pub event: i64,
+    pub name: String,
+    pub mistake: String,
+    pub reporter: String,
+    pub context: Option<String>,
+    /// Unknown for reports from before the mistakes log, which no date range includes
+    pub timestamp: Option<String>,
+    /// Retracted reports stay in the log but are no longer counted
+    pub retracted: bool,
+}
+
+/// Changes to a reported mistake, fields left out keep their current value
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct MistakeCorrection {
+    pub name: Option<String>,
+    pub mistake: Option<String>,
+}
+
+#[tsync]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum CorrectionAction {
+    Retracted,
+    Corrected,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct MistakeCorrectionRecord {
+    pub id: i64,
+    pub report_id: i64,
+    pub action: CorrectionAction,
+    pub old_name: String,
+    pub old_mistake: String,
+    pub new_name: Option<String>,
+    pub new_mistake: Option<String>,
+    pub moderator: String,
+    pub timestamp: String,
+}
+
+#[tsync]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppealStatus {
+    Pending,
+    /// The report stands
+    Upheld,
+    /// The report was retracted
+    Overturned,
+}
+
+/// A participant disputing a mistake report
+#[tsync]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewAppeal {
+    pub reason: String,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct Appeal {
+    pub id: i64,
+    pub report: LoggedMistake,
+    pub reason: String,
+    pub appellant: String,
+    pub status: AppealStatus,
+    pub moderator: Option<String>,
+    /// The moderator's explanation of their decision
+    pub response: Option<String>,
+    pub created_at: String,
+    pub decided_at: Option<String>,
+}
+
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct AppealQuery {
+    pub event: Option<i64>,
+    /// All appeals unless set
+    pub status: Option<AppealStatus>,
+}
+
+#[tsync]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppealDecision {
+    /// Retracts the report if set, otherwise upholds it
+    pub overturn: bool,
+    pub response: Option<String>,
+}
+
 #[tsync]
 #[derive(Debug, Serialize)]
 pub struct PersonMistakes {
@@ -62,7 +245,7 @@ pub struct Translation {
 }
 
 #[tsync]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TranslationAddition {
     pub translation: Translation,
     pub suggestor: String,
@@ -76,6 +259,15 @@ pub struct TranslationSuggestion {
     pub hebrew: String,
 }
 
+/// Accepting a suggestion may override its Hebrew translation
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct DiscardTranslationSuggestion {
+    pub id: i64,
+    pub accepted: bool,
+    pub hebrew: Option<String>,
+}
+
 #[tsync]
 #[derive(Debug, Serialize)]
 pub struct SuggestedTranslation {
@@ -83,9 +275,280 @@ pub struct SuggestedTranslation {
     pub suggestor: String,
 }
 
+/// Narrows the pending suggestions down to those matching every given field
+#[tsync]
+#[derive(Debug, Default, Deserialize)]
+pub struct SuggestionFilter {
+    /// Also matches mistake suggestions this reporter witnessed, for moderators
+    pub reporter: Option<String>,
+    /// Mistake suggestions only
+    pub participant: Option<String>,
+    /// Mistakes match by canonical word where known, translations by either side
+    pub word: Option<String>,
+}
+
+/// Accepts or rejects many suggestions at once, selected by id, by filter, or both
 #[tsync]
 #[derive(Debug, Deserialize)]
+pub struct SuggestionBatch {
+    pub ids: Option<Vec<i64>>,
+    #[serde(default)]
+    pub filter: SuggestionFilter,
+    pub accepted: bool,
+    /// Mistake suggestions only, defaults to the active event
+    pub event: Option<i64>,
+}
+
+/// What happened to one suggestion of a batch
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub id: i64,
+    /// Only set if it failed, in which case it was left pending
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorBody>,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub succeeded: u32,
+    pub failed: u32,
+    pub results: Vec<BatchItemResult>,
+}
+
+#[tsync]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CanonicalRequest {
     pub word: String,
     pub canonical: String,
 }
+
+#[tsync]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventStatus {
+    Planned,
+    Active,
+    Finished,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct WeekEvent {
+    pub id: i64,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub status: EventStatus,
+}
+
+#[tsync]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewWeekEvent {
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+/// Selects the event a request refers to, defaulting to the active one
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct EventQuery {
+    pub event: Option<i64>,
+}
+
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct MistakesLogQuery {
+    pub event: Option<i64>,
+    pub word: Option<String>,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntry {
+    /// Participants with the same total share a rank
+    pub rank: u32,
+    pub name: String,
+    pub total: u32,
+    pub distinct_words: u32,
+    pub top_word: Option<String>,
+}
+
+/// Dates are inclusive and formatted as YYYY-MM-DD
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    pub event: Option<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// What an admin may do, a `SuperAdmin` may do everything
+#[tsync]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    /// Moderates participants, mistakes and their suggestions
+    Moderator,
+    /// Maintains translations, their suggestions and canonical words
+    DictionaryEditor,
+    SuperAdmin,
+}
+
+#[tsync]
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminUser {
+    pub username: String,
+    pub role: Role,
+}
+
+#[tsync]
+#[derive(Deserialize)]
+pub struct NewAdminUser {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+}
+
+#[tsync]
+#[derive(Deserialize)]
+pub struct Login {
+    pub username: String,
+    pub password: String,
+    /// From the authenticator app, or a recovery code. Required once TOTP is enrolled.
+    pub code: Option<String>,
+}
+
+#[tsync]
+#[derive(Deserialize)]
+pub struct PasswordChange {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Shown once: the secret for the authenticator app, and recovery codes in case it's lost
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct TotpCode {
+    pub code: String,
+}
+
+/// Required to replace or remove a confirmed enrollment, so a stolen session token can't
+/// turn off the second factor: either the password or a current code
+#[tsync]
+#[derive(Debug, Default, Deserialize)]
+pub struct TotpReauthentication {
+    pub password: Option<String>,
+    pub code: Option<String>,
+}
+
+/// Sent as `Authorization: Bearer <token>` until it expires or is revoked
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct SessionToken {
+    pub token: String,
+    pub username: String,
+    pub role: Role,
+    pub expires_at: String,
+}
+
+/// What an admin (or, for suggestions, a reporter) changed
+#[tsync]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditAction {
+    AddEvent,
+    SetEventStatus,
+    AddParticipant,
+    UpdateParticipant,
+    RenameParticipant,
+    MergeParticipant,
+    RemoveParticipant,
+    ReportMistake,
+    RetractMistake,
+    CorrectMistake,
+    SuggestMistake,
+    AcceptMistakeSuggestion,
+    RejectMistakeSuggestion,
+    SuggestTranslation,
+    AddTranslation,
+    AcceptTranslationSuggestion,
+    RejectTranslationSuggestion,
+    AddCanonical,
+    AddAdminUser,
+    RemoveAdminUser,
+    ChangePassword,
+    Login,
+    Logout,
+    RevokeSessions,
+    EnrollTotp,
+    ConfirmTotp,
+    RemoveTotp,
+    BlockReporter,
+    UnblockReporter,
+    AutoApproveMistakeSuggestion,
+    RevertAutoApproval,
+    WitnessMistakeSuggestion,
+    AppealMistake,
+    UpholdAppeal,
+    OverturnAppeal,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub ip: Option<String>,
+    pub action: AuditAction,
+    /// JSON of what was changed, before and after the change
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub timestamp: String,
+}
+
+#[tsync]
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    /// Inclusive dates, as YYYY-MM-DD
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Starting from 1, newest entries first
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    pub total: u32,
+    pub page: u32,
+    pub page_size: u32,
+}
+
+/// A reporter (or IP) whose suggestions are refused
+#[tsync]
+#[derive(Debug, Serialize)]
+pub struct BlockedReporter {
+    pub reporter: String,
+    pub reason: Option<String>,
+    pub blocked_by: String,
+    pub timestamp: String,
+}
+
+#[tsync]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewBlockedReporter {
+    pub reporter: String,
+    pub reason: Option<String>,
+}